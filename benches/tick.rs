@@ -0,0 +1,122 @@
+//! Compares one polling cycle of the deadline queue ([`TimerLoop::tick`]) with the
+//! full-map scan the loop used to run on every refresh.
+use std::{
+    hint::black_box,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use criterion::{BatchSize, BenchmarkId, Criterion, criterion_group, criterion_main};
+use dashmap::DashMap;
+use sand_clock::{
+    SandClockInsertion, SystemClock,
+    scheduler::DeadlineQueue,
+    stats::Counters,
+    timer_loop::TimerLoop,
+    user_table::{InsertSync, Slot, TimerStatus},
+};
+
+type Map = Arc<DashMap<InsertSync<u64>, Slot<()>>>;
+type Queue = Arc<Mutex<DeadlineQueue<InsertSync<u64>>>>;
+
+/// Fills a map with `size` keys, one in `due_every` of them already timed out.
+fn populate(size: u64, due_every: u64) -> (Map, Queue) {
+    let map: Map = Arc::new(DashMap::new());
+    let queue: Queue = Arc::new(Mutex::new(DeadlineQueue::new()));
+    let mut locked_queue = queue.lock().unwrap();
+    for key in 0..size {
+        let time_out = if key % due_every == 0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs(3600)
+        };
+        let mut status = TimerStatus::new(time_out, &SystemClock);
+        let deadline = status.schedule().unwrap();
+        locked_queue.push(key.to_insert_sync(), deadline);
+        map.insert(key.to_insert_sync(), (status, Some(())));
+    }
+    drop(locked_queue);
+    (map, queue)
+}
+
+/// The previous polling cycle: every entry is visited under a write lock.
+fn scan_tick(map: &Map, now: Instant) -> usize {
+    let mut expired_queue = vec![];
+    for mut entry in map.iter_mut() {
+        let (status, _) = entry.value_mut();
+        if status.is_expired() {
+            continue;
+        }
+        if status.is_timed_out(now) {
+            status.expired();
+            expired_queue.push(entry.key().clone());
+        }
+    }
+    let expired = expired_queue.len();
+    for key in expired_queue {
+        map.remove(&key);
+    }
+    expired
+}
+
+fn tick(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tick");
+    group.sample_size(20);
+
+    for size in [10_000u64, 100_000] {
+        // steady state: keys are alive, nothing is due.
+        let (map, queue) = populate(size, u64::MAX);
+        group.bench_with_input(BenchmarkId::new("scan_idle", size), &map, |b, map| {
+            b.iter(|| black_box(scan_tick(map, Instant::now())));
+        });
+        let (job_sender, _job_receiver) = crossbeam_channel::unbounded();
+        let mut timer_loop = TimerLoop::new(
+            "sand_clock",
+            &map,
+            &queue,
+            &Arc::new(Counters::default()),
+            job_sender,
+        );
+        group.bench_function(BenchmarkId::new("deadline_queue_idle", size), |b| {
+            b.iter(|| black_box(timer_loop.tick(Instant::now())));
+        });
+
+        // 1% of the keys time out in this tick.
+        group.bench_function(BenchmarkId::new("scan_1%_due", size), |b| {
+            b.iter_batched(
+                || populate(size, 100).0,
+                // the map is returned so that dropping it is not measured.
+                |map| (black_box(scan_tick(&map, Instant::now())), map),
+                BatchSize::LargeInput,
+            );
+        });
+        group.bench_function(BenchmarkId::new("deadline_queue_1%_due", size), |b| {
+            b.iter_batched(
+                || {
+                    let (map, queue) = populate(size, 100);
+                    let (job_sender, job_receiver) = crossbeam_channel::unbounded();
+                    let timer_loop = TimerLoop::new(
+                        "sand_clock",
+                        &map,
+                        &queue,
+                        &Arc::new(Counters::default()),
+                        job_sender,
+                    );
+                    (timer_loop, job_receiver)
+                },
+                |(mut timer_loop, job_receiver)| {
+                    (
+                        black_box(timer_loop.tick(Instant::now())),
+                        timer_loop,
+                        job_receiver,
+                    )
+                },
+                BatchSize::LargeInput,
+            );
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, tick);
+criterion_main!(benches);
@@ -0,0 +1,150 @@
+//! `Time sources`
+use std::{
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+/// One polling cycle of a [`SandClock`](crate::SandClock), run at the given instant.
+///
+/// Returns `false` once the `SandClock` is dropped and the loop must not run anymore.
+pub type Tick = Box<dyn FnMut(Instant) -> bool + Send + 'static>;
+
+/// Source of time of a [`SandClock`](crate::SandClock).
+///
+/// [`Timer`](crate::user_table::Timer) reads the time of each signal from it, and the timer loop
+/// checks the deadlines against it. The default is [`SystemClock`]; tests can use a
+/// [`ManualClock`] to control time by hand.
+pub trait Clock: Send + Sync + 'static {
+    /// Returns the current instant of this clock.
+    fn now(&self) -> Instant;
+    /// Offers the polling loop to the clock.
+    ///
+    /// A clock that drives the loop itself keeps `tick` and returns `Ok(())`. The default
+    /// hands it back, and the `SandClock` then runs it on its own polling thread.
+    ///
+    /// # Errors
+    /// Returns `tick` back if the clock does not drive the loop.
+    fn drive(&self, tick: Tick) -> Result<(), Tick> {
+        Err(tick)
+    }
+}
+
+/// The wall clock: [`Instant::now()`], with the loop polled on a background thread.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock that only moves when [`ManualClock::advance()`] is called.
+///
+/// A `SandClock` built on a `ManualClock` spawns no polling thread: each call to `advance`
+/// runs the polling cycle on the calling thread, so every key due at the new instant is
+/// expired before `advance` returns. Timeouts can be tested in microseconds, without sleeping.
+///
+/// Clones share the same time.
+///
+/// ### Example
+/// ```rust
+/// use std::time::Duration;
+/// use sand_clock::{ClockEvent, ManualClock, SandClock, SandClockConfig, TimeOutInfo};
+///
+/// let clock = ManualClock::new();
+/// let (sand_clock, events) = SandClock::<u32>::new(SandClockConfig::default())
+///     .set_time_out_duration(Duration::from_secs(30))
+///     .set_clock(clock.clone())
+///     .build_with_receiver()
+///     .unwrap();
+///
+/// sand_clock.insert_or_update_timer(1);
+/// clock.advance(Duration::from_secs(29));
+/// assert!(events.try_recv().is_err());
+///
+/// clock.advance(Duration::from_secs(1));
+/// assert!(matches!(events.try_recv(), Ok(ClockEvent::TimeOut(TimeOutInfo { key: 1, .. }, _))));
+/// ```
+#[derive(Clone)]
+pub struct ManualClock {
+    now: Arc<Mutex<Instant>>,
+    ticks: Arc<Ticks>,
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self {
+            now: Arc::new(Mutex::new(Instant::now())),
+            ticks: Arc::new(Ticks::default()),
+        }
+    }
+}
+
+impl ManualClock {
+    /// Creates a manual clock, starting at the current instant.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Moves the clock forward by `duration`, then runs the polling cycle of every
+    /// `SandClock` built on it.
+    ///
+    /// The polling cycles run on the calling thread. A `SandClock` that was dropped
+    /// sends its [`ClockEvent::SandClockDrop`](crate::ClockEvent::SandClockDrop) on the next advance.
+    ///
+    /// A `duration` that would move the clock beyond the range of [`Instant`] leaves it
+    /// where it is, and runs no polling cycle.
+    pub fn advance(&self, duration: Duration) {
+        let now = {
+            let mut now = self.now.lock().unwrap_or_else(PoisonError::into_inner);
+            let Some(later) = now.checked_add(duration) else {
+                return;
+            };
+            *now = later;
+            later
+        };
+        self.ticks.run(now);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        *self.now.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+    fn drive(&self, tick: Tick) -> Result<(), Tick> {
+        self.ticks.register(tick)
+    }
+}
+
+/// The polling cycles registered on a clock that drives them.
+#[derive(Default)]
+pub(crate) struct Ticks {
+    ticks: Mutex<Vec<Tick>>,
+}
+
+impl Ticks {
+    /// Adds a polling cycle, or hands it back if the set is poisoned.
+    pub(crate) fn register(&self, tick: Tick) -> Result<(), Tick> {
+        match self.ticks.lock() {
+            Ok(mut ticks) => {
+                ticks.push(tick);
+                Ok(())
+            }
+            Err(_) => Err(tick),
+        }
+    }
+    /// Runs every polling cycle at `now`, and forgets the ones of the dropped clocks.
+    pub(crate) fn run(&self, now: Instant) {
+        // the ticks are run out of the lock, so a callback may use the clock.
+        let mut ticks = self
+            .ticks
+            .lock()
+            .map(|mut ticks| std::mem::take(&mut *ticks))
+            .unwrap_or_default();
+        ticks.retain_mut(|tick| tick(now));
+        if let Ok(mut registered) = self.ticks.lock() {
+            registered.append(&mut ticks);
+        }
+    }
+}
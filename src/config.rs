@@ -1,6 +1,8 @@
 //! `SandClock Configuration`
 use std::time::Duration;
 
+use crate::executor::CallBackExecutor;
+
 /// Configuration object for a [`SandClock`] instance.
 ///
 /// `SandClockConfig` allows you to control the internal polling loop
@@ -25,6 +27,33 @@ use std::time::Duration;
 #[derive(Clone)]
 pub struct SandClockConfig {
     refresh_duration: Duration,
+    shutdown_policy: ShutdownPolicy,
+    executor: CallBackExecutor,
+    polling_mode: PollingMode,
+    name: String,
+}
+
+/// What [`SandClock::shutdown()`](crate::SandClock::shutdown) does with the events that
+/// are still waiting to be delivered.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ShutdownPolicy {
+    /// Delivers every pending event before returning.
+    #[default]
+    Drain,
+    /// Discards the pending events. Only the final `ClockEvent::SandClockDrop` is delivered.
+    Cancel,
+}
+
+/// When the polling thread of a [`SandClock`](crate::SandClock) wakes up.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PollingMode {
+    /// Every refresh duration set with [`SandClockConfig::frequency()`].
+    #[default]
+    Fixed,
+    /// At the earliest deadline of the clock, or when a key with an earlier deadline is
+    /// inserted. An empty clock does not wake up at all, and keys expire without waiting
+    /// for the next period.
+    Adaptive,
 }
 
 impl Default for SandClockConfig {
@@ -39,6 +68,10 @@ impl Default for SandClockConfig {
     fn default() -> Self {
         Self {
             refresh_duration: Duration::from_millis(1000),
+            shutdown_policy: ShutdownPolicy::default(),
+            executor: CallBackExecutor::default(),
+            polling_mode: PollingMode::default(),
+            name: "sand_clock".to_string(),
         }
     }
 }
@@ -64,4 +97,64 @@ impl SandClockConfig {
         self.refresh_duration = frequence_duration;
         self
     }
+    /// Returns what a shutdown does with the pending events.
+    #[must_use]
+    pub fn get_shutdown_policy(&self) -> ShutdownPolicy {
+        self.shutdown_policy
+    }
+    /// Sets what [`SandClock::shutdown()`](crate::SandClock::shutdown) does with the events
+    /// still waiting to be delivered. Defaults to [`ShutdownPolicy::Drain`].
+    #[must_use]
+    pub fn shutdown_policy(mut self, shutdown_policy: ShutdownPolicy) -> Self {
+        self.shutdown_policy = shutdown_policy;
+        self
+    }
+    /// Returns where the timeout callback runs.
+    #[must_use]
+    pub fn get_executor(&self) -> &CallBackExecutor {
+        &self.executor
+    }
+    /// Sets where the timeout callback runs. Defaults to a pool of 4 threads per clock.
+    ///
+    /// ### Example
+    /// ```rust
+    /// use std::sync::Arc;
+    /// use sand_clock::{CallBackExecutor, SandClockConfig};
+    ///
+    /// // two clocks, one pool.
+    /// let pool = Arc::new(rayon::ThreadPoolBuilder::new().num_threads(2).build().unwrap());
+    /// let sessions = SandClockConfig::new().executor(CallBackExecutor::Pool(pool.clone()));
+    /// let devices = SandClockConfig::new().executor(CallBackExecutor::Pool(pool));
+    /// ```
+    #[must_use]
+    pub fn executor(mut self, executor: CallBackExecutor) -> Self {
+        self.executor = executor;
+        self
+    }
+    /// Returns when the polling thread wakes up.
+    #[must_use]
+    pub fn get_polling_mode(&self) -> PollingMode {
+        self.polling_mode
+    }
+    /// Sets when the polling thread wakes up. Defaults to [`PollingMode::Fixed`].
+    ///
+    /// Has no effect on a clock driven by a [`ManualClock`](crate::ManualClock) or a
+    /// [`TimerDriver`](crate::TimerDriver), which polls at its own pace.
+    #[must_use]
+    pub fn polling_mode(mut self, polling_mode: PollingMode) -> Self {
+        self.polling_mode = polling_mode;
+        self
+    }
+    /// Returns the name of the clock.
+    #[must_use]
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+    /// Names the clock, to tell its metrics and tracing spans apart from the ones of the
+    /// other clocks of the application. Defaults to `sand_clock`.
+    #[must_use]
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
 }
@@ -0,0 +1,172 @@
+//! `Phi-accrual failure detection`
+use std::{collections::VecDeque, time::Duration};
+
+/// Settings of the phi-accrual failure detector, set with
+/// `SandClockBuilder::set_phi_accrual()`.
+///
+/// Instead of a fixed timeout, each key learns the distribution of the intervals between its
+/// signals, and computes from the silence since its last signal a suspicion level, phi: the
+/// key is unlikely to be alive with a probability of `1 - 10^-phi`. It times out when phi
+/// reaches the threshold, so a key with jittery signals gets more slack than a regular one.
+///
+/// The defaults are the ones of Akka: a threshold of 8, 1000 samples, a standard deviation of
+/// at least 100ms, no acceptable pause, and a first interval estimated to 1 second.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PhiAccrual {
+    threshold: f64,
+    max_sample_size: usize,
+    min_std_deviation: Duration,
+    acceptable_heartbeat_pause: Duration,
+    first_heartbeat_estimate: Duration,
+}
+
+impl Default for PhiAccrual {
+    fn default() -> Self {
+        Self {
+            threshold: 8.0,
+            max_sample_size: 1000,
+            min_std_deviation: Duration::from_millis(100),
+            acceptable_heartbeat_pause: Duration::ZERO,
+            first_heartbeat_estimate: Duration::from_secs(1),
+        }
+    }
+}
+
+impl PhiAccrual {
+    /// Creates detector settings timing keys out when phi reaches `threshold`.
+    #[must_use]
+    pub fn new(threshold: f64) -> Self {
+        Self {
+            threshold,
+            ..Self::default()
+        }
+    }
+    /// Returns the suspicion level at which a key times out.
+    #[must_use]
+    pub fn get_threshold(&self) -> f64 {
+        self.threshold
+    }
+    /// Sets the number of intervals kept per key to estimate their distribution.
+    #[must_use]
+    pub fn max_sample_size(mut self, max_sample_size: usize) -> Self {
+        self.max_sample_size = max_sample_size.max(1);
+        self
+    }
+    /// Sets the lowest standard deviation used, so that very regular signals do not make
+    /// the detector oversensitive.
+    #[must_use]
+    pub fn min_std_deviation(mut self, min_std_deviation: Duration) -> Self {
+        self.min_std_deviation = min_std_deviation;
+        self
+    }
+    /// Sets a silence that is tolerated on top of the mean interval, e.g. for GC pauses.
+    #[must_use]
+    pub fn acceptable_heartbeat_pause(mut self, acceptable_heartbeat_pause: Duration) -> Self {
+        self.acceptable_heartbeat_pause = acceptable_heartbeat_pause;
+        self
+    }
+    /// Sets the interval expected before a key has signaled twice.
+    #[must_use]
+    pub fn first_heartbeat_estimate(mut self, first_heartbeat_estimate: Duration) -> Self {
+        self.first_heartbeat_estimate = first_heartbeat_estimate;
+        self
+    }
+}
+
+/// The last intervals between the signals of a key, in seconds.
+#[derive(Clone, Debug)]
+pub(crate) struct HeartbeatHistory {
+    settings: PhiAccrual,
+    intervals: VecDeque<f64>,
+    sum: f64,
+    squared_sum: f64,
+    /// Silence after which phi reaches the threshold, updated with each interval.
+    time_to_threshold: Duration,
+}
+
+impl HeartbeatHistory {
+    /// Creates a history seeded with the first heartbeat estimate of `settings`.
+    pub(crate) fn new(settings: PhiAccrual) -> Self {
+        let mut history = Self {
+            settings,
+            intervals: VecDeque::new(),
+            sum: 0.0,
+            squared_sum: 0.0,
+            time_to_threshold: Duration::ZERO,
+        };
+        // two samples around the estimate, with a standard deviation of a quarter of it.
+        let mean = settings.first_heartbeat_estimate.as_secs_f64();
+        let std_deviation = mean / 4.0;
+        history.push(mean - std_deviation);
+        history.push(mean + std_deviation);
+        history.time_to_threshold = history.compute_time_to_threshold();
+        history
+    }
+    /// Records the interval between two signals.
+    pub(crate) fn record(&mut self, interval: Duration) {
+        self.push(interval.as_secs_f64());
+        self.time_to_threshold = self.compute_time_to_threshold();
+    }
+    /// Returns the silence after which phi reaches the threshold.
+    pub(crate) fn time_to_threshold(&self) -> Duration {
+        self.time_to_threshold
+    }
+    /// Returns the suspicion level after `elapsed` of silence.
+    pub(crate) fn phi(&self, elapsed: Duration) -> f64 {
+        self.phi_at((elapsed.as_secs_f64() - self.mean()) / self.std_deviation())
+    }
+    fn push(&mut self, interval: f64) {
+        if self.intervals.len() >= self.settings.max_sample_size
+            && let Some(oldest) = self.intervals.pop_front()
+        {
+            self.sum -= oldest;
+            self.squared_sum -= oldest * oldest;
+        }
+        self.intervals.push_back(interval);
+        self.sum += interval;
+        self.squared_sum += interval * interval;
+    }
+    /// Mean interval, acceptable pause included.
+    fn mean(&self) -> f64 {
+        self.sum / self.intervals.len() as f64
+            + self.settings.acceptable_heartbeat_pause.as_secs_f64()
+    }
+    fn std_deviation(&self) -> f64 {
+        let len = self.intervals.len() as f64;
+        let mean = self.sum / len;
+        let variance = (self.squared_sum / len - mean * mean).max(0.0);
+        variance
+            .sqrt()
+            .max(self.settings.min_std_deviation.as_secs_f64())
+            // a null deviation would make every silence infinitely suspect.
+            .max(f64::EPSILON)
+    }
+    /// Phi at `y` standard deviations from the mean, with the logistic approximation of the
+    /// normal distribution used by Akka.
+    fn phi_at(&self, y: f64) -> f64 {
+        let e = (-y * (1.5976 + 0.070566 * y * y)).exp();
+        if y > 0.0 {
+            -(e / (1.0 + e)).log10()
+        } else {
+            -(1.0 - 1.0 / (1.0 + e)).log10()
+        }
+    }
+    /// Solves `phi = threshold` by bisection, phi growing with the silence.
+    fn compute_time_to_threshold(&self) -> Duration {
+        let threshold = self.settings.threshold;
+        let (mut low, mut high) = (-64.0_f64, 1.0_f64);
+        while self.phi_at(high) < threshold && high < 1e9 {
+            high *= 2.0;
+        }
+        for _ in 0..64 {
+            let middle = (low + high) / 2.0;
+            if self.phi_at(middle) < threshold {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+        let seconds = self.mean() + high * self.std_deviation();
+        Duration::try_from_secs_f64(seconds.max(0.0)).unwrap_or(Duration::MAX)
+    }
+}
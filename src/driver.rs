@@ -0,0 +1,87 @@
+//! `Shared timer driver`
+use std::{
+    sync::{Arc, Weak},
+    time::{Duration, Instant},
+};
+
+use crate::clock::{Clock, Tick, Ticks};
+
+/// A wall clock whose single background thread polls every [`SandClock`](crate::SandClock)
+/// built on it, whatever their key and value types.
+///
+/// By default each `SandClock` polls its keys on a thread of its own. Giving the same
+/// driver to many clocks with `SandClockBuilder::set_clock()` replaces all those threads by
+/// the one of the driver, which polls at its own frequency: the frequency set in the config
+/// of each clock is not used.
+///
+/// The driver only replaces the polling threads. Where the callback of each clock runs
+/// depends on its [`CallBackExecutor`](crate::CallBackExecutor):
+///
+/// - `Inline`: on the thread of the driver, after the tick of the clock. No thread is
+///   spawned, but the callbacks of all the clocks run one after the other.
+/// - a `Pool` or `Custom` executor shared by the clocks: the events of each tick are handed to
+///   it as one job per clock, delivered in order. No thread is spawned per clock.
+/// - `Threads(n)`, the default: a dispatch thread and a pool of `n` threads **per clock**, so
+///   thousands of clocks still mean thousands of sleeping threads. The event stream of
+///   `events()` also keeps a dispatch thread per clock.
+///
+/// With `build_with_receiver()`, a clock spawns no thread either. With `Inline`, `Pool` or
+/// `Custom`, the events sent between two ticks, such as `Inserted`, are delivered after the
+/// next tick.
+///
+/// The thread stops once the driver and all the clocks built on it are dropped. Clones
+/// share the same thread.
+///
+/// ### Example
+/// ```rust
+/// use std::{sync::Arc, time::Duration};
+/// use sand_clock::{CallBackExecutor, SandClock, SandClockConfig, TimerDriver};
+///
+/// let driver = TimerDriver::new(Duration::from_millis(200));
+/// let pool = Arc::new(rayon::ThreadPoolBuilder::new().num_threads(2).build().unwrap());
+/// let config = SandClockConfig::default().executor(CallBackExecutor::Pool(pool));
+///
+/// let sessions = SandClock::<u64>::new(config.clone())
+///     .set_time_out_event(|_clock_event| {})
+///     .set_time_out_duration(Duration::from_secs(30))
+///     .set_clock(driver.clone())
+///     .build()
+///     .unwrap();
+/// let devices = SandClock::<String>::new(config)
+///     .set_time_out_event(|_clock_event| {})
+///     .set_time_out_duration(Duration::from_secs(60))
+///     .set_clock(driver)
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Clone)]
+pub struct TimerDriver {
+    ticks: Arc<Ticks>,
+}
+
+impl TimerDriver {
+    /// Starts a driver polling the clocks registered on it every `frequency`.
+    #[must_use]
+    pub fn new(frequency: Duration) -> Self {
+        let ticks = Arc::new(Ticks::default());
+        let driven: Weak<Ticks> = Arc::downgrade(&ticks);
+        std::thread::spawn(move || {
+            // the thread does not keep the driver alive.
+            while let Some(ticks) = driven.upgrade() {
+                ticks.run(Instant::now());
+                drop(ticks);
+                std::thread::sleep(frequency);
+            }
+        });
+        Self { ticks }
+    }
+}
+
+impl Clock for TimerDriver {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+    fn drive(&self, tick: Tick) -> Result<(), Tick> {
+        self.ticks.register(tick)
+    }
+}
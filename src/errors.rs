@@ -5,6 +5,8 @@ pub enum SandClockError {
     InsertionFailure,
     BuildErrorNoDurationSet,
     BuildErrorNoTimeOutSet,
+    #[cfg(feature = "stream")]
+    NoEventStream,
     Io(std::io::Error),
 }
 
@@ -23,6 +25,10 @@ impl Display for SandClockError {
             SandClockError::BuildErrorNoDurationSet => {
                 write!(f, "User connected base : Build error  No Duration set !")
             }
+            #[cfg(feature = "stream")]
+            SandClockError::NoEventStream => {
+                write!(f, "No event stream set on the builder, or already taken !")
+            }
 
             SandClockError::Io(e) => {
                 write!(f, "Io error [{:?}]", e.to_string())
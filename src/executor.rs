@@ -0,0 +1,113 @@
+//! `Callback executors`
+use std::{
+    any::Any,
+    panic::{self, AssertUnwindSafe},
+    sync::Arc,
+};
+
+use log::info;
+
+use crate::{SandClockInsertion, stats::Counters, user_table::TimeOutCallBack};
+
+/// Runs the timeout callbacks of a [`SandClock`](crate::SandClock) on a user-provided
+/// executor, see [`CallBackExecutor::Custom`].
+///
+/// The dispatch thread hands one job per event to `execute`, and waits for the job to run
+/// before handing the next one: events are delivered one at a time, in order. A clock that
+/// drives its loop, such as a [`TimerDriver`](crate::TimerDriver), has no dispatch thread:
+/// the events of each polling cycle are handed as one job, and the next job is only handed
+/// once it ran. A job that is dropped without being run counts as delivered.
+pub trait Executor: Send + Sync + 'static {
+    /// Runs `job`, on any thread.
+    fn execute(&self, job: Box<dyn FnOnce() + Send + 'static>);
+}
+
+/// Where the timeout callback of a [`SandClock`](crate::SandClock) runs, set with
+/// [`SandClockConfig::executor()`](crate::SandClockConfig::executor).
+///
+/// Several clocks can share one executor by cloning the same `Pool` or `Custom` into their
+/// configs.
+#[derive(Clone)]
+pub enum CallBackExecutor {
+    /// A rayon pool of the given number of threads, owned by the clock, fed by a dispatch
+    /// thread of the clock.
+    Threads(usize),
+    /// A rayon pool shared with the rest of the application.
+    ///
+    /// A clock driven by a [`TimerDriver`](crate::TimerDriver) or a
+    /// [`ManualClock`](crate::ManualClock) hands its events to the pool after each polling
+    /// cycle. Otherwise a dispatch thread of the clock feeds the pool.
+    Pool(Arc<rayon::ThreadPool>),
+    /// The polling thread, right after each polling cycle: no thread is spawned for the
+    /// callback, which delays the next cycle while it runs.
+    ///
+    /// With a [`ManualClock`](crate::ManualClock), the callback runs inside `advance()`.
+    Inline,
+    /// A user-provided [`Executor`], fed like a shared `Pool`.
+    Custom(Arc<dyn Executor>),
+}
+
+impl Default for CallBackExecutor {
+    /// A pool of 4 threads per clock.
+    fn default() -> Self {
+        Self::Threads(4)
+    }
+}
+
+/// A panic of the timeout callback, passed to the hook set with
+/// `SandClockBuilder::on_callback_error()`.
+///
+/// The panic is caught for each event: the dispatcher keeps delivering the next ones.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CallBackPanic {
+    /// Kind of the event the callback was handling, see `ClockEvent::kind()`.
+    pub event: &'static str,
+    /// Message of the panic, if it was a string.
+    pub message: Option<String>,
+}
+
+/// Hook called with each panic of the timeout callback.
+pub type CallBackErrorHook = Arc<dyn Fn(CallBackPanic) + Send + Sync + 'static>;
+
+/// Wraps `call_back` to catch its panics, count them in `counters`, and report them to
+/// `on_error`.
+pub(crate) fn guard_call_back<K: SandClockInsertion, V: 'static>(
+    call_back: TimeOutCallBack<K, V>,
+    on_error: Option<CallBackErrorHook>,
+    counters: Arc<Counters>,
+) -> TimeOutCallBack<K, V> {
+    Arc::new(move |clock_event| {
+        let event = clock_event.kind();
+        // a panic does not poison the callback: it still receives the next events.
+        let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| (*call_back)(clock_event)))
+        else {
+            return;
+        };
+        counters.panicked();
+        let call_back_panic = CallBackPanic {
+            event,
+            message: panic_message(payload.as_ref()),
+        };
+        let Some(on_error) = &on_error else {
+            info!("the callback panicked [{call_back_panic:?}]");
+            return;
+        };
+        // the hook is guarded too, a panic there must not stop the dispatch of the events.
+        if let Err(payload) =
+            panic::catch_unwind(AssertUnwindSafe(|| (**on_error)(call_back_panic.clone())))
+        {
+            info!(
+                "the callback error hook panicked on [{call_back_panic:?}] [{:?}]",
+                panic_message(payload.as_ref())
+            );
+        }
+    })
+}
+
+/// Returns the message of a panic, if it was a string.
+fn panic_message(payload: &(dyn Any + Send)) -> Option<String> {
+    payload
+        .downcast_ref::<&str>()
+        .map(ToString::to_string)
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+}
@@ -18,10 +18,10 @@
 //! //Instantiate the SandClock, with the key type as generic argument.
 //! let user_connection_base = SandClock::<String>::new(config)
 //!    .set_time_out_event(move |clock_event| match clock_event {
-//!        ClockEvent::TimeOut(key) => {
+//!        ClockEvent::TimeOut(TimeOutInfo { key, .. }, _) => {
 //!            println!("No more known activity: [{:?}] has disconnected", key);
 //!        }
-//!         ClockEvent::SandClockDrop => {}
+//!         _ => {}
 //!    })
 //!    .set_time_out_duration(Duration::from_millis(15_000))
 //!    .build()
@@ -37,11 +37,15 @@
 //! ```
 //!  ⚙️ Runtime-free design: `SandClock` uses a single background thread for polling and `rayon::ThreadPool` to run the timeouts callbacks.
 //!
+//! To keep a value next to each key (a session, a device descriptor...), build the clock with
+//! [`SandClock::with_values()`]: the value is handed back with its key in [`ClockEvent::TimeOut`].
+//!
 //! ## Quick links
 //!
 //! - [`SandClock`] — main entry point, used to insert or update tracked entities
 //! - [`SandClockConfig`] — configures the loop frequency
 //! - [`ClockEvent`] — type of events triggered on timeout
+//! - [`ManualClock`] — source of time moved by hand, to test timeouts without sleeping
 //! - [`TimeOutUpdate`] — passed to your callback when a timeout occurs
 //!
 //! ## How it works
@@ -51,7 +55,8 @@
 //! If signaling stops within a defined timeout, `SandClock` **automatically triggers a callback**, notifying the caller that the entity is no longer active.
 //! The entity is then removed from the map.
 //!
-//! Internally, `SandClock` uses a lightweight polling loop to monitor timeouts +
+//! Internally, `SandClock` uses a lightweight polling loop to monitor timeouts, driven by a
+//! [`scheduler::DeadlineQueue`] so that each tick only visits the keys that are due +
 //! `rayon::ThreadPool`
 //! to manage timout-callbacks.
 //!
@@ -68,12 +73,23 @@
 //!
 //!
 
+pub mod clock;
 pub mod config;
+pub mod detector;
+pub mod driver;
 
 pub mod errors;
+pub mod executor;
+pub mod scheduler;
+pub mod snapshot;
+pub mod stats;
+#[cfg(feature = "metrics")]
+pub mod telemetry;
 #[cfg(test)]
 mod test;
 pub mod timer_loop;
+#[cfg(feature = "tracing")]
+pub mod trace;
 pub mod user_table;
 
 //pub use config::SandClockConfig;
@@ -82,14 +98,45 @@ pub mod user_table;
 
 pub mod prelude {
     pub use super::{
-        config::SandClockConfig, errors::SandClockError, user_table::ClockEvent,
-        user_table::InsertSync, user_table::SandClock, user_table::SandClockInsertion,
+        clock::{Clock, ManualClock, SystemClock},
+        config::{PollingMode, SandClockConfig, ShutdownPolicy},
+        detector::PhiAccrual,
+        driver::TimerDriver,
+        errors::SandClockError,
+        executor::{CallBackExecutor, CallBackPanic, Executor},
+        snapshot::{Snapshot, SnapshotEntry},
+        stats::SandClockStats,
+        timer_loop::ShutdownReport,
+        user_table::ClockEvent,
+        user_table::EntryInfo,
+        user_table::EventReceiver,
+        user_table::ExpiryPolicy,
+        user_table::InsertSync,
+        user_table::SandClock,
+        user_table::SandClockInsertion,
+        user_table::TimeOutInfo,
+        user_table::WeakSandClock,
     };
 }
 
 pub use {
-    config::SandClockConfig, errors::SandClockError, user_table::ClockEvent, user_table::SandClock,
+    clock::{Clock, ManualClock, SystemClock},
+    config::{PollingMode, SandClockConfig, ShutdownPolicy},
+    detector::PhiAccrual,
+    driver::TimerDriver,
+    errors::SandClockError,
+    executor::{CallBackExecutor, CallBackPanic, Executor},
+    snapshot::{Snapshot, SnapshotEntry},
+    stats::SandClockStats,
+    timer_loop::ShutdownReport,
+    user_table::ClockEvent,
+    user_table::EntryInfo,
+    user_table::EventReceiver,
+    user_table::ExpiryPolicy,
+    user_table::SandClock,
     user_table::SandClockInsertion,
+    user_table::TimeOutInfo,
+    user_table::WeakSandClock,
 };
 
 use user_table::InsertSync;
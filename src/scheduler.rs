@@ -0,0 +1,97 @@
+//! `Deadline scheduling`
+use std::{cmp::Reverse, collections::BinaryHeap, time::Instant};
+
+/// Deadline-ordered queue used by the timer loop to find the keys that are due.
+///
+/// Instead of scanning every entry of the map on each tick, the loop only pops the keys
+/// whose scheduled deadline is reached, so a tick costs about the number of expiring keys.
+///
+/// The queue is lazy: refreshing a key does not move it in the queue. When a key is popped,
+/// the loop checks the real deadline stored in its [`TimerStatus`] and pushes it back if
+/// the key has been refreshed meanwhile. A key is only pushed again ahead of time when its
+/// deadline gets *earlier* (e.g. a shorter timeout duration).
+///
+/// Removed keys, and keys pushed again with an earlier deadline, leave stale entries behind.
+/// The timer loop drops them once they outnumber the tracked keys, so the queue holds at
+/// most about twice as many entries as the map.
+///
+/// [`TimerStatus`]: crate::user_table::TimerStatus
+pub struct DeadlineQueue<K> {
+    heap: BinaryHeap<Reverse<Scheduled<K>>>,
+}
+
+struct Scheduled<K> {
+    deadline: Instant,
+    key: K,
+}
+
+impl<K> PartialEq for Scheduled<K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.deadline == other.deadline
+    }
+}
+impl<K> Eq for Scheduled<K> {}
+impl<K> PartialOrd for Scheduled<K> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl<K> Ord for Scheduled<K> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.deadline.cmp(&other.deadline)
+    }
+}
+
+impl<K> Default for DeadlineQueue<K> {
+    fn default() -> Self {
+        Self {
+            heap: BinaryHeap::new(),
+        }
+    }
+}
+
+impl<K> DeadlineQueue<K> {
+    /// Creates an empty queue.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Schedules `key` to be checked at `deadline`.
+    pub fn push(&mut self, key: K, deadline: Instant) {
+        self.heap.push(Reverse(Scheduled { deadline, key }));
+    }
+    /// Pops the next key whose deadline is reached at `now`, with the deadline it was
+    /// scheduled for.
+    pub fn pop_due(&mut self, now: Instant) -> Option<(K, Instant)> {
+        if self.heap.peek()?.0.deadline > now {
+            return None;
+        }
+        self.heap
+            .pop()
+            .map(|Reverse(scheduled)| (scheduled.key, scheduled.deadline))
+    }
+    /// Returns the earliest scheduled deadline, if any.
+    #[must_use]
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.heap.peek().map(|scheduled| scheduled.0.deadline)
+    }
+    /// Keeps only the entries for which `keep` returns `true`, given the key and the
+    /// deadline it was scheduled for.
+    pub fn retain(&mut self, mut keep: impl FnMut(&K, Instant) -> bool) {
+        self.heap
+            .retain(|Reverse(scheduled)| keep(&scheduled.key, scheduled.deadline));
+    }
+    /// Moves all the entries of `other` into this queue, leaving `other` empty.
+    pub fn append(&mut self, other: &mut Self) {
+        self.heap.append(&mut other.heap);
+    }
+    /// Returns the number of scheduled entries, stale ones included.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+}
@@ -0,0 +1,54 @@
+//! `Snapshot of the tracked keys`
+use std::time::Duration;
+
+use crate::user_table::ExpiryPolicy;
+
+/// The keys tracked by a [`SandClock`](crate::SandClock) at a given time, taken with
+/// `SandClock::snapshot()` and restored with `SandClockBuilder::restore_from()`.
+///
+/// Timers are saved as durations relative to the time of the snapshot, not as [`Instant`]s,
+/// which do not survive a process restart. A restored key keeps the time to live it had
+/// when the snapshot was taken.
+///
+/// With the `serde` feature, a snapshot can be serialized in any serde format.
+///
+/// [`Instant`]: std::time::Instant
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Snapshot<K, V = ()> {
+    pub entries: Vec<SnapshotEntry<K, V>>,
+}
+
+/// A key of a [`Snapshot`], with its value and timer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SnapshotEntry<K, V = ()> {
+    pub key: K,
+    pub value: V,
+    /// Time elapsed since the last signal of the key, when the snapshot was taken.
+    pub elapsed: Duration,
+    /// Time elapsed since the insertion of the key, when the snapshot was taken.
+    pub age: Duration,
+    /// Inactivity duration after which the key times out.
+    pub time_out_duration: Duration,
+    /// How the deadline of the key is computed.
+    pub expiry_policy: ExpiryPolicy,
+}
+
+impl<K, V> Default for Snapshot<K, V> {
+    fn default() -> Self {
+        Self { entries: vec![] }
+    }
+}
+
+impl<K, V> Snapshot<K, V> {
+    /// Returns the number of keys in the snapshot.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
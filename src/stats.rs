@@ -0,0 +1,132 @@
+//! `Statistics of a clock`
+use std::{
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+    time::Duration,
+};
+
+#[cfg(feature = "metrics")]
+use crate::telemetry::ClockMetrics;
+
+/// Counters of a [`SandClock`](crate::SandClock), taken with `SandClock::stats()`.
+///
+/// The totals count since the clock was built, restored keys included in `inserts`. A key
+/// leaves the map through a timeout or a removal, never both, so
+/// `entries == inserts - timeouts - removals` once the clock is idle.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SandClockStats {
+    /// Keys currently tracked.
+    pub entries: usize,
+    /// Keys inserted.
+    pub inserts: u64,
+    /// Signals of tracked keys.
+    pub refreshes: u64,
+    /// Keys expired by the timer loop.
+    pub timeouts: u64,
+    /// Keys removed with `remove_key()`, `remove()` or `retain()`.
+    pub removals: u64,
+    /// Panics of the callback, caught by the dispatcher.
+    pub callback_panics: u64,
+    /// Events sent but not yet taken by the dispatch thread, or by the user in channel mode.
+    pub queued_events: usize,
+    /// Time spent in the last polling cycle.
+    pub last_tick_duration: Duration,
+}
+
+/// The live counters shared by a clock and its timer loop.
+///
+/// Each count is updated while the map entry of its key is locked, together with the
+/// insertion, signal or removal it counts: a key that leaves the map is counted once.
+///
+/// With the `metrics` feature, the counts are also published as the metrics of the clock.
+#[derive(Debug)]
+pub struct Counters {
+    entries: AtomicUsize,
+    inserts: AtomicU64,
+    refreshes: AtomicU64,
+    timeouts: AtomicU64,
+    removals: AtomicU64,
+    callback_panics: AtomicU64,
+    last_tick_nanos: AtomicU64,
+    #[cfg(feature = "metrics")]
+    metrics: ClockMetrics,
+}
+
+impl Default for Counters {
+    fn default() -> Self {
+        Self::new("sand_clock")
+    }
+}
+
+impl Counters {
+    /// Creates the counters of the clock `name`, whose metrics are labelled with it.
+    #[must_use]
+    #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+    pub fn new(name: &str) -> Self {
+        Self {
+            entries: AtomicUsize::new(0),
+            inserts: AtomicU64::new(0),
+            refreshes: AtomicU64::new(0),
+            timeouts: AtomicU64::new(0),
+            removals: AtomicU64::new(0),
+            callback_panics: AtomicU64::new(0),
+            last_tick_nanos: AtomicU64::new(0),
+            #[cfg(feature = "metrics")]
+            metrics: ClockMetrics::new(name),
+        }
+    }
+    pub(crate) fn inserted(&self) {
+        self.entries.fetch_add(1, Ordering::Relaxed);
+        self.inserts.fetch_add(1, Ordering::Relaxed);
+        #[cfg(feature = "metrics")]
+        self.metrics.inserted();
+    }
+    pub(crate) fn refreshed(&self) {
+        self.refreshes.fetch_add(1, Ordering::Relaxed);
+        #[cfg(feature = "metrics")]
+        self.metrics.refreshed();
+    }
+    /// Counts a timeout, fired `lateness` after the deadline of the key.
+    #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+    pub(crate) fn timed_out(&self, lateness: Duration) {
+        self.entries.fetch_sub(1, Ordering::Relaxed);
+        self.timeouts.fetch_add(1, Ordering::Relaxed);
+        #[cfg(feature = "metrics")]
+        self.metrics.timed_out(lateness);
+    }
+    pub(crate) fn removed(&self) {
+        self.entries.fetch_sub(1, Ordering::Relaxed);
+        self.removals.fetch_add(1, Ordering::Relaxed);
+        #[cfg(feature = "metrics")]
+        self.metrics.removed();
+    }
+    pub(crate) fn panicked(&self) {
+        self.callback_panics.fetch_add(1, Ordering::Relaxed);
+        #[cfg(feature = "metrics")]
+        self.metrics.panicked();
+    }
+    /// Records a polling cycle, after which `queued_events` are waiting for delivery.
+    #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+    pub(crate) fn ticked(&self, duration: Duration, queued_events: usize) {
+        let nanos = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+        self.last_tick_nanos.store(nanos, Ordering::Relaxed);
+        #[cfg(feature = "metrics")]
+        self.metrics.ticked(duration, queued_events);
+    }
+    /// Returns the number of keys currently tracked.
+    pub(crate) fn entries(&self) -> usize {
+        self.entries.load(Ordering::Relaxed)
+    }
+    /// Reads the counters, with the number of events waiting in the job channel.
+    pub(crate) fn stats(&self, queued_events: usize) -> SandClockStats {
+        SandClockStats {
+            entries: self.entries(),
+            inserts: self.inserts.load(Ordering::Relaxed),
+            refreshes: self.refreshes.load(Ordering::Relaxed),
+            timeouts: self.timeouts.load(Ordering::Relaxed),
+            removals: self.removals.load(Ordering::Relaxed),
+            callback_panics: self.callback_panics.load(Ordering::Relaxed),
+            queued_events,
+            last_tick_duration: Duration::from_nanos(self.last_tick_nanos.load(Ordering::Relaxed)),
+        }
+    }
+}
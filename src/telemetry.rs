@@ -0,0 +1,106 @@
+//! `Metrics of a clock`, published with the `metrics` feature.
+//!
+//! Each clock registers its metrics with the recorder installed when it is built, labelled
+//! with `clock = <name>`, the name set with [`SandClockConfig::name()`]:
+//!
+//! | metric | type | |
+//! |---|---|---|
+//! | `sand_clock_entries` | gauge | keys currently tracked |
+//! | `sand_clock_inserts_total` | counter | keys inserted |
+//! | `sand_clock_refreshes_total` | counter | signals of tracked keys |
+//! | `sand_clock_timeouts_total` | counter | keys expired by the timer loop |
+//! | `sand_clock_removals_total` | counter | keys removed explicitly |
+//! | `sand_clock_callback_panics_total` | counter | panics of the callback |
+//! | `sand_clock_queued_events` | gauge | events waiting for delivery, after each polling cycle |
+//! | `sand_clock_tick_duration_seconds` | histogram | time spent in each polling cycle |
+//! | `sand_clock_expiry_lateness_seconds` | histogram | delay between the deadline of a key and its timeout |
+//!
+//! Any `metrics` exporter can publish them, e.g. `metrics-exporter-prometheus` for an
+//! OpenMetrics endpoint.
+//!
+//! [`SandClockConfig::name()`]: crate::SandClockConfig::name
+use std::time::Duration;
+
+use metrics::{Counter, Gauge, Histogram, Unit};
+
+/// The handles of the metrics of one clock.
+#[derive(Debug)]
+pub(crate) struct ClockMetrics {
+    entries: Gauge,
+    inserts: Counter,
+    refreshes: Counter,
+    timeouts: Counter,
+    removals: Counter,
+    callback_panics: Counter,
+    queued_events: Gauge,
+    tick_duration: Histogram,
+    expiry_lateness: Histogram,
+}
+
+impl ClockMetrics {
+    /// Registers the metrics of the clock `name` with the current recorder.
+    pub(crate) fn new(name: &str) -> Self {
+        Self::describe();
+        let label = [("clock", name.to_string())];
+        Self {
+            entries: metrics::gauge!("sand_clock_entries", &label),
+            inserts: metrics::counter!("sand_clock_inserts_total", &label),
+            refreshes: metrics::counter!("sand_clock_refreshes_total", &label),
+            timeouts: metrics::counter!("sand_clock_timeouts_total", &label),
+            removals: metrics::counter!("sand_clock_removals_total", &label),
+            callback_panics: metrics::counter!("sand_clock_callback_panics_total", &label),
+            queued_events: metrics::gauge!("sand_clock_queued_events", &label),
+            tick_duration: metrics::histogram!("sand_clock_tick_duration_seconds", &label),
+            expiry_lateness: metrics::histogram!("sand_clock_expiry_lateness_seconds", &label),
+        }
+    }
+    fn describe() {
+        metrics::describe_gauge!("sand_clock_entries", "Keys currently tracked.");
+        metrics::describe_counter!("sand_clock_inserts_total", "Keys inserted.");
+        metrics::describe_counter!("sand_clock_refreshes_total", "Signals of tracked keys.");
+        metrics::describe_counter!(
+            "sand_clock_timeouts_total",
+            "Keys expired by the timer loop."
+        );
+        metrics::describe_counter!("sand_clock_removals_total", "Keys removed explicitly.");
+        metrics::describe_counter!(
+            "sand_clock_callback_panics_total",
+            "Panics of the callback."
+        );
+        metrics::describe_gauge!("sand_clock_queued_events", "Events waiting for delivery.");
+        metrics::describe_histogram!(
+            "sand_clock_tick_duration_seconds",
+            Unit::Seconds,
+            "Time spent in each polling cycle."
+        );
+        metrics::describe_histogram!(
+            "sand_clock_expiry_lateness_seconds",
+            Unit::Seconds,
+            "Delay between the deadline of a key and its timeout."
+        );
+    }
+    pub(crate) fn inserted(&self) {
+        self.entries.increment(1.0);
+        self.inserts.increment(1);
+    }
+    pub(crate) fn refreshed(&self) {
+        self.refreshes.increment(1);
+    }
+    pub(crate) fn timed_out(&self, lateness: Duration) {
+        self.entries.decrement(1.0);
+        self.timeouts.increment(1);
+        self.expiry_lateness.record(lateness);
+    }
+    pub(crate) fn removed(&self) {
+        self.entries.decrement(1.0);
+        self.removals.increment(1);
+    }
+    pub(crate) fn panicked(&self) {
+        self.callback_panics.increment(1);
+    }
+    pub(crate) fn ticked(&self, duration: Duration, queued_events: usize) {
+        self.tick_duration.record(duration);
+        #[allow(clippy::cast_precision_loss)]
+        self.queued_events.set(queued_events as f64);
+    }
+}
@@ -53,7 +53,7 @@ fn test_timer() {
     {
         let user_connection_base = SandClock::<String>::new(config)
             .set_time_out_event(move |clock_event| match clock_event {
-                ClockEvent::TimeOut(key) => {
+                ClockEvent::TimeOut(TimeOutInfo { key, .. }, _) => {
                     println!("has_deconnected [{:?}]", key);
                     if let Err(e) = sender.send((key, true)) {
                         println!("Failed to send key deconnection info [{e:?}]")
@@ -62,6 +62,7 @@ fn test_timer() {
                 ClockEvent::SandClockDrop => {
                     println!("Clock has dropped");
                 }
+                _ => {}
             })
             .set_time_out_duration(time_out_duration)
             .build()
@@ -110,3 +111,1310 @@ fn test_timer() {
     );
     assert!(user_0_deconnection_confirmed && !user_1_deconnection_confirmed);
 }
+
+#[test]
+fn per_key_time_out() {
+    let (sender, receiver) = crossbeam_channel::unbounded::<&'static str>();
+    let config = SandClockConfig::new().frequency(Duration::from_millis(50));
+    let sand_clock = SandClock::<&'static str>::new(config)
+        .set_time_out_event(move |clock_event| {
+            if let ClockEvent::TimeOut(TimeOutInfo { key, .. }, _) = clock_event {
+                let _ = sender.send(key);
+            }
+        })
+        .set_time_out_duration(Duration::from_secs(10))
+        .build()
+        .unwrap();
+
+    sand_clock.insert_or_update_timer("default");
+    sand_clock.insert_or_update_timer_with_timeout("short", Duration::from_millis(300));
+
+    let key = receiver.recv_timeout(Duration::from_secs(2)).unwrap();
+    assert_eq!(key, "short");
+    assert!(receiver.recv_timeout(Duration::from_millis(500)).is_err());
+    assert!(sand_clock.contains_key("default"));
+}
+
+#[test]
+fn per_key_tiers_with_values() {
+    // a value without `Default`.
+    struct Session(&'static str);
+
+    let clock = ManualClock::new();
+    let (sessions, events) = SandClock::<u32, Session>::with_values(SandClockConfig::default())
+        .set_time_out_duration(Duration::from_secs(10))
+        .set_clock(clock.clone())
+        .build_with_receiver()
+        .unwrap();
+
+    sessions.insert(0, Session("anonymous"));
+    assert!(
+        sessions
+            .insert_with_timeout(1, Session("service"), Duration::from_secs(60))
+            .is_none()
+    );
+    sessions.insert_with_policy(2, Session("guest"), ExpiryPolicy::Absolute);
+    clock.advance(Duration::from_secs(8));
+    // the signals of the guest do not push its deadline back.
+    sessions.insert(0, Session("anonymous"));
+    sessions.insert(2, Session("guest"));
+    clock.advance(Duration::from_secs(4));
+
+    let Ok(ClockEvent::TimeOut(info, session)) = events.try_recv() else {
+        panic!("the guest session did not time out");
+    };
+    assert_eq!((info.key, session.0), (2, "guest"));
+    assert!(events.try_recv().is_err());
+    assert_eq!(sessions.time_remaining(&1), Some(Duration::from_secs(48)));
+}
+
+#[test]
+fn stale_deadlines_are_compacted() {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{
+        scheduler::DeadlineQueue,
+        stats::Counters,
+        timer_loop::TimerLoop,
+        user_table::{Slot, TimerStatus},
+    };
+
+    let map = Arc::new(dashmap::DashMap::<InsertSync<u32>, Slot<()>>::new());
+    let queue = Arc::new(Mutex::new(DeadlineQueue::new()));
+    let (job_sender, _job_receiver) = crossbeam_channel::unbounded();
+    let mut timer_loop = TimerLoop::new(
+        "sand_clock",
+        &map,
+        &queue,
+        &Arc::new(Counters::default()),
+        job_sender,
+    );
+    let mut status = TimerStatus::new(Duration::from_secs(10), &SystemClock);
+    let deadline = status.schedule().unwrap();
+    map.insert(0.to_insert_sync(), (status, Some(())));
+    queue.lock().unwrap().push(0.to_insert_sync(), deadline);
+
+    // a key removed before its deadline stays queued until the stale entries outnumber the
+    // tracked keys.
+    queue.lock().unwrap().push(1.to_insert_sync(), deadline);
+    timer_loop.tick(Instant::now());
+    assert_eq!(queue.lock().unwrap().len(), 2);
+
+    queue.lock().unwrap().push(2.to_insert_sync(), deadline);
+    timer_loop.tick(Instant::now());
+    assert_eq!(queue.lock().unwrap().len(), 1);
+    assert_eq!(queue.lock().unwrap().next_deadline(), Some(deadline));
+}
+
+#[test]
+fn values_handed_back_on_time_out() {
+    let (sender, receiver) = crossbeam_channel::unbounded::<(u32, String)>();
+    let config = SandClockConfig::new().frequency(Duration::from_millis(50));
+    let sand_clock = SandClock::<u32, String>::with_values(config)
+        .set_time_out_event(move |clock_event| {
+            if let ClockEvent::TimeOut(TimeOutInfo { key, .. }, value) = clock_event {
+                let _ = sender.send((key, value));
+            }
+        })
+        .set_time_out_duration(Duration::from_millis(300))
+        .build()
+        .unwrap();
+
+    assert!(sand_clock.insert(1, "alf".to_string()).is_none());
+    assert!(sand_clock.insert(2, "camille".to_string()).is_none());
+    assert_eq!(
+        sand_clock.insert(1, "alf_2".to_string()).as_deref(),
+        Some("alf")
+    );
+
+    sand_clock.get_mut(&2).unwrap().push_str("_2");
+    assert_eq!(sand_clock.get(&2).unwrap().as_str(), "camille_2");
+    assert_eq!(sand_clock.remove(&2).as_deref(), Some("camille_2"));
+    assert!(sand_clock.get(&2).is_none());
+    assert_eq!(sand_clock.get_entries_count(), 1);
+
+    let expired = receiver.recv_timeout(Duration::from_secs(2)).unwrap();
+    assert_eq!(expired, (1, "alf_2".to_string()));
+    assert!(!sand_clock.contains_key(1));
+    assert_eq!(sand_clock.get_entries_count(), 0);
+}
+
+#[test]
+fn shorter_time_out_is_rescheduled() {
+    let (sender, receiver) = crossbeam_channel::unbounded::<u8>();
+    let config = SandClockConfig::new().frequency(Duration::from_millis(50));
+    let sand_clock = SandClock::<u8>::new(config)
+        .set_time_out_event(move |clock_event| {
+            if let ClockEvent::TimeOut(TimeOutInfo { key, .. }, _) = clock_event {
+                let _ = sender.send(key);
+            }
+        })
+        .set_time_out_duration(Duration::from_secs(10))
+        .build()
+        .unwrap();
+
+    sand_clock.insert_or_update_timer(0);
+    sand_clock.insert_or_update_timer(1);
+    // moves the deadline of key 1 before the one it was scheduled with.
+    sand_clock.insert_or_update_timer_with_timeout(1, Duration::from_millis(200));
+
+    assert_eq!(receiver.recv_timeout(Duration::from_secs(2)), Ok(1));
+    assert!(sand_clock.contains_key(0));
+    assert_eq!(sand_clock.get_entries_count(), 1);
+}
+
+#[cfg(feature = "stream")]
+#[test]
+fn event_stream() {
+    use futures::StreamExt;
+
+    let config = SandClockConfig::new().frequency(Duration::from_millis(50));
+    let sand_clock = SandClock::<u8>::new(config)
+        .set_event_stream(1)
+        .set_time_out_duration(Duration::from_millis(100))
+        .build()
+        .unwrap();
+    let mut events = sand_clock.events().unwrap();
+    assert!(sand_clock.events().is_err());
+
+    for key in 0..4 {
+        sand_clock.insert_or_update_timer(key);
+    }
+    // the stream holds a single event, the others wait for the consumer.
+    std::thread::sleep(Duration::from_millis(400));
+    let mut timed_out: Vec<u8> = futures::executor::block_on(
+        events
+            .by_ref()
+            .take(4)
+            .filter_map(|clock_event| async move {
+                match clock_event {
+                    ClockEvent::TimeOut(TimeOutInfo { key, .. }, _) => Some(key),
+                    _ => None,
+                }
+            })
+            .collect(),
+    );
+    timed_out.sort_unstable();
+    assert_eq!(timed_out, vec![0, 1, 2, 3]);
+
+    drop(sand_clock);
+    let last_events: Vec<_> = futures::executor::block_on(events.collect());
+    assert!(matches!(
+        last_events.as_slice(),
+        [ClockEvent::SandClockDrop]
+    ));
+}
+
+#[test]
+fn event_receiver() {
+    let config = SandClockConfig::new().frequency(Duration::from_millis(50));
+    let (sand_clock, events) = SandClock::<u8, &'static str>::with_values(config)
+        .set_time_out_duration(Duration::from_millis(100))
+        .build_with_receiver()
+        .unwrap();
+
+    sand_clock.insert(1, "alf");
+    assert!(matches!(
+        events.recv_timeout(Duration::from_secs(2)),
+        Ok(ClockEvent::TimeOut(TimeOutInfo { key: 1, .. }, "alf"))
+    ));
+
+    drop(sand_clock);
+    assert!(matches!(
+        events.recv_timeout(Duration::from_secs(2)),
+        Ok(ClockEvent::SandClockDrop)
+    ));
+}
+
+#[test]
+fn manual_clock() {
+    let clock = ManualClock::new();
+    let (sand_clock, events) = SandClock::<&'static str>::new(SandClockConfig::default())
+        .set_time_out_duration(Duration::from_secs(6))
+        .set_clock(clock.clone())
+        .build_with_receiver()
+        .unwrap();
+
+    sand_clock.insert_or_update_timer("camille");
+    sand_clock.insert_or_update_timer("alf");
+
+    clock.advance(Duration::from_secs(4));
+    sand_clock.insert_or_update_timer("camille");
+    assert!(events.try_recv().is_err());
+
+    clock.advance(Duration::from_secs(2));
+    assert!(matches!(
+        events.try_recv(),
+        Ok(ClockEvent::TimeOut(TimeOutInfo { key: "alf", .. }, _))
+    ));
+    assert!(events.try_recv().is_err());
+    assert!(sand_clock.contains_key("camille"));
+
+    clock.advance(Duration::from_secs(4));
+    assert!(matches!(
+        events.try_recv(),
+        Ok(ClockEvent::TimeOut(TimeOutInfo { key: "camille", .. }, _))
+    ));
+    assert_eq!(sand_clock.get_entries_count(), 0);
+
+    // an advance out of the range of `Instant` leaves the clock where it is.
+    let now = clock.now();
+    clock.advance(Duration::MAX);
+    assert_eq!(clock.now(), now);
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(clock.now(), now + Duration::from_secs(1));
+
+    drop(sand_clock);
+    clock.advance(Duration::from_secs(1));
+    assert!(matches!(events.try_recv(), Ok(ClockEvent::SandClockDrop)));
+}
+
+#[test]
+fn snapshot_and_restore() {
+    let clock = ManualClock::new();
+    let (sand_clock, _events) = SandClock::<String, u32>::with_values(SandClockConfig::default())
+        .set_time_out_duration(Duration::from_secs(10))
+        .set_clock(clock.clone())
+        .build_with_receiver()
+        .unwrap();
+    sand_clock.insert("alf".to_string(), 1);
+    clock.advance(Duration::from_secs(4));
+    sand_clock.insert("camille".to_string(), 2);
+
+    let mut snapshot = sand_clock.snapshot();
+    drop(sand_clock);
+    snapshot.entries.sort_by(|a, b| a.key.cmp(&b.key));
+    assert_eq!(
+        snapshot.entries,
+        vec![
+            SnapshotEntry {
+                key: "alf".to_string(),
+                value: 1,
+                elapsed: Duration::from_secs(4),
+                age: Duration::from_secs(4),
+                time_out_duration: Duration::from_secs(10),
+                expiry_policy: ExpiryPolicy::Sliding,
+            },
+            SnapshotEntry {
+                key: "camille".to_string(),
+                value: 2,
+                elapsed: Duration::ZERO,
+                age: Duration::ZERO,
+                time_out_duration: Duration::from_secs(10),
+                expiry_policy: ExpiryPolicy::Sliding,
+            },
+        ]
+    );
+
+    // a restart: the new clock has a time of its own.
+    let clock = ManualClock::new();
+    let (sand_clock, events) = SandClock::<String, u32>::with_values(SandClockConfig::default())
+        .set_time_out_duration(Duration::from_secs(10))
+        .set_clock(clock.clone())
+        .restore_from(snapshot)
+        .build_with_receiver()
+        .unwrap();
+    assert_eq!(sand_clock.get_entries_count(), 2);
+
+    clock.advance(Duration::from_secs(6));
+    assert!(
+        matches!(events.try_recv(), Ok(ClockEvent::TimeOut(TimeOutInfo { key, .. }, 1)) if key == "alf")
+    );
+    assert!(events.try_recv().is_err());
+    clock.advance(Duration::from_secs(4));
+    assert!(
+        matches!(events.try_recv(), Ok(ClockEvent::TimeOut(TimeOutInfo { key, .. }, 2)) if key == "camille")
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn snapshot_serde() {
+    let snapshot = Snapshot {
+        entries: vec![SnapshotEntry {
+            key: 7u64,
+            value: "alf".to_string(),
+            elapsed: Duration::from_millis(1500),
+            age: Duration::from_secs(60),
+            time_out_duration: Duration::from_secs(30),
+            expiry_policy: ExpiryPolicy::SlidingWithCap(Duration::from_secs(3600)),
+        }],
+    };
+    let json = serde_json::to_string(&snapshot).unwrap();
+    let restored: Snapshot<u64, String> = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored, snapshot);
+}
+
+#[test]
+fn shutdown() {
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    let clock = ManualClock::new();
+    let time_outs = Arc::new(AtomicUsize::new(0));
+    let time_outs_0 = time_outs.clone();
+    let sand_clock = SandClock::<u32>::new(SandClockConfig::default())
+        .set_time_out_event(move |clock_event| {
+            if let ClockEvent::TimeOut(..) = clock_event {
+                std::thread::sleep(Duration::from_millis(20));
+                time_outs_0.fetch_add(1, Ordering::Relaxed);
+            }
+        })
+        .set_time_out_duration(Duration::from_secs(1))
+        .set_clock(clock.clone())
+        .build()
+        .unwrap();
+    for key in 0..5 {
+        sand_clock.insert_or_update_timer(key);
+    }
+    clock.advance(Duration::from_secs(1));
+
+    // every pending event is delivered before shutdown returns.
+    let report = sand_clock.shutdown();
+    assert_eq!(
+        report,
+        ShutdownReport {
+            delivered: 5,
+            dropped: 0
+        }
+    );
+    assert_eq!(time_outs.load(Ordering::Relaxed), 5);
+
+    let time_outs = Arc::new(AtomicUsize::new(0));
+    let time_outs_0 = time_outs.clone();
+    let (started_sender, started) = crossbeam_channel::unbounded();
+    let sand_clock =
+        SandClock::<u32>::new(SandClockConfig::default().shutdown_policy(ShutdownPolicy::Cancel))
+            .set_time_out_event(move |clock_event| {
+                if let ClockEvent::TimeOut(..) = clock_event {
+                    let _ = started_sender.send(());
+                    std::thread::sleep(Duration::from_millis(100));
+                    time_outs_0.fetch_add(1, Ordering::Relaxed);
+                }
+            })
+            .set_time_out_duration(Duration::from_secs(1))
+            .build()
+            .unwrap();
+    for key in 0..5 {
+        sand_clock.insert_or_update_timer(key);
+    }
+    started.recv_timeout(Duration::from_secs(5)).unwrap();
+
+    // the event being delivered ends, the others are discarded.
+    let report = sand_clock.shutdown();
+    assert_eq!(report.delivered + report.dropped, 5);
+    assert!(report.dropped >= 3);
+    assert_eq!(time_outs.load(Ordering::Relaxed), report.delivered);
+
+    // inline, the events still pending when the clock shuts down are discarded too.
+    let clock = ManualClock::new();
+    let events = Arc::new(AtomicUsize::new(0));
+    let events_0 = events.clone();
+    let sand_clock = SandClock::<u32>::new(
+        SandClockConfig::default()
+            .executor(CallBackExecutor::Inline)
+            .shutdown_policy(ShutdownPolicy::Cancel),
+    )
+    .set_time_out_event(move |clock_event| {
+        if !matches!(clock_event, ClockEvent::SandClockDrop) {
+            events_0.fetch_add(1, Ordering::Relaxed);
+        }
+    })
+    .set_lifecycle_events()
+    .set_time_out_duration(Duration::from_secs(1))
+    .set_clock(clock.clone())
+    .build()
+    .unwrap();
+    sand_clock.insert_or_update_timer(0);
+    clock.advance(Duration::from_secs(1));
+    // the insertions wait for the next polling cycle, which never comes.
+    for key in 1..4 {
+        sand_clock.insert_or_update_timer(key);
+    }
+    let report = sand_clock.shutdown();
+    assert_eq!(
+        report,
+        ShutdownReport {
+            delivered: 2,
+            dropped: 3
+        }
+    );
+    assert_eq!(events.load(Ordering::Relaxed), 2);
+}
+
+#[test]
+fn last_handle_stops_the_loop() {
+    let clock = ManualClock::new();
+    let (sand_clock, events) = SandClock::<u32>::new(SandClockConfig::default())
+        .set_time_out_duration(Duration::from_secs(1))
+        .set_clock(clock.clone())
+        .build_with_receiver()
+        .unwrap();
+    let weak = sand_clock.downgrade();
+
+    let clone = sand_clock.clone();
+    std::thread::spawn(move || clone.insert_or_update_timer(1))
+        .join()
+        .unwrap();
+
+    // the clone is dropped, the loop goes on.
+    clock.advance(Duration::from_secs(1));
+    assert!(matches!(
+        events.try_recv(),
+        Ok(ClockEvent::TimeOut(TimeOutInfo { key: 1, .. }, _))
+    ));
+
+    weak.upgrade().unwrap().insert_or_update_timer(2);
+    clock.advance(Duration::from_secs(1));
+    assert!(matches!(
+        events.try_recv(),
+        Ok(ClockEvent::TimeOut(TimeOutInfo { key: 2, .. }, _))
+    ));
+
+    // a weak handle does not keep the loop alive.
+    drop(sand_clock);
+    assert!(weak.upgrade().is_none());
+    clock.advance(Duration::from_secs(1));
+    assert!(matches!(events.try_recv(), Ok(ClockEvent::SandClockDrop)));
+}
+
+#[test]
+fn time_out_info() {
+    let clock = ManualClock::new();
+    let (sand_clock, events) = SandClock::<u32>::new(SandClockConfig::default())
+        .set_time_out_duration(Duration::from_secs(5))
+        .set_clock(clock.clone())
+        .build_with_receiver()
+        .unwrap();
+
+    let first_seen = clock.now();
+    sand_clock.insert_or_update_timer(1);
+    clock.advance(Duration::from_secs(3));
+    sand_clock.insert_or_update_timer(1);
+    clock.advance(Duration::from_secs(3));
+    sand_clock.insert_or_update_timer(1);
+    let last_seen = clock.now();
+
+    // the deadline is passed by 2 seconds when the expiry fires.
+    clock.advance(Duration::from_secs(7));
+    let Ok(ClockEvent::TimeOut(info, _)) = events.try_recv() else {
+        panic!("key 1 should have timed out");
+    };
+    assert_eq!(info.key, 1);
+    assert_eq!(info.first_seen, first_seen);
+    assert_eq!(info.last_seen, last_seen);
+    assert_eq!(info.heartbeats, 3);
+    assert_eq!(info.deadline, last_seen + Duration::from_secs(5));
+    assert_eq!(info.lifetime(), Duration::from_secs(6));
+    assert_eq!(info.lateness(), Duration::from_secs(2));
+}
+
+#[test]
+fn callback_executors() {
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    // inline: the callback runs inside `advance`, on the calling thread.
+    let clock = ManualClock::new();
+    let caller = std::thread::current().id();
+    let time_outs = Arc::new(AtomicUsize::new(0));
+    let time_outs_0 = time_outs.clone();
+    let sand_clock =
+        SandClock::<u32>::new(SandClockConfig::default().executor(CallBackExecutor::Inline))
+            .set_time_out_event(move |clock_event| {
+                if let ClockEvent::TimeOut(..) = clock_event {
+                    assert_eq!(std::thread::current().id(), caller);
+                    time_outs_0.fetch_add(1, Ordering::Relaxed);
+                }
+            })
+            .set_time_out_duration(Duration::from_secs(1))
+            .set_clock(clock.clone())
+            .build()
+            .unwrap();
+    sand_clock.insert_or_update_timer(1);
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(time_outs.load(Ordering::Relaxed), 1);
+    assert_eq!(sand_clock.shutdown().delivered, 1);
+
+    // two clocks share one pool.
+    let pool = Arc::new(
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap(),
+    );
+    let config = SandClockConfig::default().executor(CallBackExecutor::Pool(pool.clone()));
+    let clocks: Vec<_> = (0..2)
+        .map(|_| {
+            let time_outs = time_outs.clone();
+            let pool = pool.clone();
+            SandClock::<u32>::new(config.clone())
+                .set_time_out_event(move |clock_event| {
+                    if let ClockEvent::TimeOut(..) = clock_event {
+                        assert_eq!(pool.current_thread_index(), Some(0));
+                        time_outs.fetch_add(1, Ordering::Relaxed);
+                    }
+                })
+                .set_time_out_duration(Duration::from_secs(1))
+                .set_clock(clock.clone())
+                .build()
+                .unwrap()
+        })
+        .collect();
+    for sand_clock in &clocks {
+        sand_clock.insert_or_update_timer(1);
+    }
+    clock.advance(Duration::from_secs(1));
+    for sand_clock in clocks {
+        assert_eq!(sand_clock.shutdown().delivered, 1);
+    }
+    assert_eq!(time_outs.load(Ordering::Relaxed), 3);
+
+    // custom: the events go through the executor, one job per polling cycle of the clock.
+    struct Spawner(Arc<AtomicUsize>);
+    impl Executor for Spawner {
+        fn execute(&self, job: Box<dyn FnOnce() + Send + 'static>) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            std::thread::spawn(job);
+        }
+    }
+    let jobs = Arc::new(AtomicUsize::new(0));
+    let sand_clock = SandClock::<u32>::new(
+        SandClockConfig::default()
+            .executor(CallBackExecutor::Custom(Arc::new(Spawner(jobs.clone())))),
+    )
+    .set_time_out_event(|_clock_event| {})
+    .set_time_out_duration(Duration::from_secs(1))
+    .set_clock(clock.clone())
+    .build()
+    .unwrap();
+    sand_clock.insert_or_update_timer(1);
+    sand_clock.insert_or_update_timer(2);
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(sand_clock.shutdown().delivered, 2);
+    // the timeouts, then the final `SandClockDrop` unless the first job was still running.
+    assert!((1..=2).contains(&jobs.load(Ordering::Relaxed)));
+}
+
+#[test]
+fn shared_timer_driver() {
+    use std::sync::{Arc, Mutex};
+
+    let driver = TimerDriver::new(Duration::from_millis(20));
+    let config = SandClockConfig::default().executor(CallBackExecutor::Inline);
+    let threads = Arc::new(Mutex::new(vec![]));
+
+    let threads_0 = threads.clone();
+    let sessions = SandClock::<u64>::new(config.clone())
+        .set_time_out_event(move |clock_event| {
+            if let ClockEvent::TimeOut(..) = clock_event {
+                threads_0.lock().unwrap().push(std::thread::current().id());
+            }
+        })
+        .set_time_out_duration(Duration::from_millis(100))
+        .set_clock(driver.clone())
+        .build()
+        .unwrap();
+    let threads_1 = threads.clone();
+    let devices = SandClock::<String>::new(config)
+        .set_time_out_event(move |clock_event| {
+            if let ClockEvent::TimeOut(..) = clock_event {
+                threads_1.lock().unwrap().push(std::thread::current().id());
+            }
+        })
+        .set_time_out_duration(Duration::from_millis(200))
+        .set_clock(driver)
+        .build()
+        .unwrap();
+
+    sessions.insert_or_update_timer(1);
+    devices.insert_or_update_timer("sensor".to_string());
+    std::thread::sleep(Duration::from_millis(500));
+
+    // both clocks are polled by the thread of the driver.
+    let threads = threads.lock().unwrap();
+    assert_eq!(threads.len(), 2);
+    assert_eq!(threads[0], threads[1]);
+    assert_ne!(threads[0], std::thread::current().id());
+    assert_eq!(
+        sessions.get_entries_count() + devices.get_entries_count(),
+        0
+    );
+
+    // with a shared pool, the events of each clock are handed to the pool in order.
+    let pool = Arc::new(
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap(),
+    );
+    let driver = TimerDriver::new(Duration::from_millis(20));
+    let (sender, events) = crossbeam_channel::unbounded();
+    let clocks: Vec<_> = (0..2)
+        .map(|clock| {
+            let sender = sender.clone();
+            let pool_0 = pool.clone();
+            SandClock::<u64>::new(
+                SandClockConfig::default().executor(CallBackExecutor::Pool(pool.clone())),
+            )
+            .set_time_out_event(move |clock_event| {
+                if pool_0.current_thread_index() == Some(0) {
+                    sender.send((clock, clock_event.kind())).unwrap();
+                }
+            })
+            .set_lifecycle_events()
+            .set_time_out_duration(Duration::from_millis(100))
+            .set_clock(driver.clone())
+            .build()
+            .unwrap()
+        })
+        .collect();
+    for sand_clock in &clocks {
+        sand_clock.insert_or_update_timer(1);
+    }
+    std::thread::sleep(Duration::from_millis(500));
+    for sand_clock in clocks {
+        assert_eq!(sand_clock.shutdown().delivered, 2);
+    }
+    let events: Vec<_> = events.try_iter().collect();
+    for clock in 0..2 {
+        let kinds: Vec<_> = events
+            .iter()
+            .filter(|(of, _)| *of == clock)
+            .map(|(_, kind)| *kind)
+            .collect();
+        assert_eq!(kinds, ["inserted", "timeout", "drop"]);
+    }
+}
+
+#[test]
+fn adaptive_polling() {
+    // a fixed loop would not wake up before 60 seconds.
+    let config = SandClockConfig::new()
+        .frequency(Duration::from_secs(60))
+        .polling_mode(PollingMode::Adaptive);
+    let (sand_clock, events) = SandClock::<u32>::new(config)
+        .set_time_out_duration(Duration::from_secs(30))
+        .build_with_receiver()
+        .unwrap();
+
+    // the loop is parked on the empty clock, then on a deadline 30 seconds away.
+    std::thread::sleep(Duration::from_millis(50));
+    sand_clock.insert_or_update_timer(1);
+    std::thread::sleep(Duration::from_millis(50));
+
+    let inserted = Instant::now();
+    sand_clock.insert_or_update_timer_with_timeout(2, Duration::from_millis(100));
+    assert!(matches!(
+        events.recv_timeout(Duration::from_secs(2)),
+        Ok(ClockEvent::TimeOut(TimeOutInfo { key: 2, .. }, _))
+    ));
+    let elapsed = inserted.elapsed();
+    assert!(elapsed >= Duration::from_millis(100));
+    assert!(elapsed < Duration::from_secs(1), "{elapsed:?}");
+    assert!(sand_clock.contains_key(1));
+}
+
+#[test]
+fn expiry_policies() {
+    let clock = ManualClock::new();
+    let (sand_clock, events) = SandClock::<&'static str>::new(SandClockConfig::default())
+        .set_time_out_duration(Duration::from_secs(10))
+        .set_expiry_policy(ExpiryPolicy::SlidingWithCap(Duration::from_secs(25)))
+        .set_clock(clock.clone())
+        .build_with_receiver()
+        .unwrap();
+
+    sand_clock.insert_or_update_timer("capped");
+    sand_clock.insert_or_update_timer_with_policy("absolute", ExpiryPolicy::Absolute);
+    sand_clock.insert_or_update_timer_with_policy("sliding", ExpiryPolicy::Sliding);
+
+    // heartbeats every 8 seconds keep the sliding keys alive, not the absolute one.
+    for _ in 0..3 {
+        clock.advance(Duration::from_secs(8));
+        for key in ["capped", "absolute", "sliding"] {
+            if sand_clock.contains_key(key) {
+                sand_clock.insert_or_update_timer(key);
+            }
+        }
+    }
+    assert!(matches!(
+        events.try_recv(),
+        Ok(ClockEvent::TimeOut(
+            TimeOutInfo {
+                key: "absolute",
+                ..
+            },
+            _
+        ))
+    ));
+    assert!(events.try_recv().is_err());
+
+    // the cap is reached 25 seconds after the insertion, despite the heartbeats.
+    clock.advance(Duration::from_secs(1));
+    assert!(matches!(
+        events.try_recv(),
+        Ok(ClockEvent::TimeOut(TimeOutInfo { key: "capped", .. }, _))
+    ));
+    assert!(sand_clock.contains_key("sliding"));
+
+    // a stricter policy moves the deadline of a tracked key earlier.
+    sand_clock.insert_or_update_timer_with_policy("sliding", ExpiryPolicy::Absolute);
+    clock.advance(Duration::from_secs(0));
+    assert!(matches!(
+        events.try_recv(),
+        Ok(ClockEvent::TimeOut(TimeOutInfo { key: "sliding", .. }, _))
+    ));
+}
+
+#[test]
+fn suspect_and_recovered() {
+    let clock = ManualClock::new();
+    let (presence, events) = SandClock::<&'static str>::new(SandClockConfig::default())
+        .set_suspect_duration(Duration::from_secs(10))
+        .set_time_out_duration(Duration::from_secs(30))
+        .set_clock(clock.clone())
+        .build_with_receiver()
+        .unwrap();
+    presence.insert_or_update_timer("alf");
+
+    clock.advance(Duration::from_secs(9));
+    assert!(events.try_recv().is_err());
+    clock.advance(Duration::from_secs(1));
+    assert!(matches!(events.try_recv(), Ok(ClockEvent::Suspect("alf"))));
+    assert!(presence.contains_key("alf"));
+
+    // a suspect key that signals again recovers, and can become suspect again.
+    clock.advance(Duration::from_secs(5));
+    presence.insert_or_update_timer("alf");
+    assert!(matches!(
+        events.try_recv(),
+        Ok(ClockEvent::Recovered("alf"))
+    ));
+    presence.insert_or_update_timer("alf");
+    assert!(events.try_recv().is_err());
+
+    clock.advance(Duration::from_secs(10));
+    assert!(matches!(events.try_recv(), Ok(ClockEvent::Suspect("alf"))));
+    clock.advance(Duration::from_secs(20));
+    assert!(matches!(
+        events.try_recv(),
+        Ok(ClockEvent::TimeOut(TimeOutInfo { key: "alf", .. }, _))
+    ));
+    assert!(events.try_recv().is_err());
+}
+
+#[test]
+fn phi_accrual() {
+    let clock = ManualClock::new();
+    let (nodes, events) = SandClock::<&'static str>::new(SandClockConfig::default())
+        .set_phi_accrual(PhiAccrual::new(8.0).first_heartbeat_estimate(Duration::from_secs(2)))
+        .set_time_out_duration(Duration::from_secs(5))
+        .set_clock(clock.clone())
+        .build_with_receiver()
+        .unwrap();
+
+    // a regular node every 2 seconds, a jittery one after 1 then 3 seconds.
+    nodes.insert_or_update_timer("regular");
+    nodes.insert_or_update_timer("jittery");
+    for _ in 0..20 {
+        clock.advance(Duration::from_secs(1));
+        nodes.insert_or_update_timer("jittery");
+        clock.advance(Duration::from_secs(1));
+        nodes.insert_or_update_timer("regular");
+        clock.advance(Duration::from_secs(2));
+        nodes.insert_or_update_timer("regular");
+        nodes.insert_or_update_timer("jittery");
+    }
+    assert!(events.try_recv().is_err());
+
+    // both nodes go silent: phi grows faster for the regular one.
+    clock.advance(Duration::from_millis(2300));
+    let (regular, jittery) = (
+        nodes.phi(&"regular").unwrap(),
+        nodes.phi(&"jittery").unwrap(),
+    );
+    assert!(regular > jittery, "{regular} <= {jittery}");
+    assert!(events.try_recv().is_err());
+
+    clock.advance(Duration::from_millis(1200));
+    assert!(matches!(
+        events.try_recv(),
+        Ok(ClockEvent::TimeOut(TimeOutInfo { key: "regular", .. }, _))
+    ));
+    assert!(events.try_recv().is_err());
+    assert!(nodes.phi(&"jittery").unwrap() < 8.0);
+
+    // the timeout duration stays an upper bound.
+    clock.advance(Duration::from_millis(1500));
+    assert!(matches!(
+        events.try_recv(),
+        Ok(ClockEvent::TimeOut(TimeOutInfo { key: "jittery", .. }, _))
+    ));
+}
+
+#[test]
+fn key_queries() {
+    let clock = ManualClock::new();
+    let sessions = SandClock::<u32>::new(SandClockConfig::default())
+        .set_time_out_event(|_| {})
+        .set_time_out_duration(Duration::from_secs(10))
+        .set_clock(clock.clone())
+        .build()
+        .unwrap();
+
+    let inserted_at = clock.now();
+    sessions.insert_or_update_timer(1);
+    clock.advance(Duration::from_secs(3));
+    assert_eq!(sessions.time_remaining(&1), Some(Duration::from_secs(7)));
+    assert_eq!(sessions.last_seen(&1), Some(inserted_at));
+    assert_eq!(sessions.age(&1), Some(Duration::from_secs(3)));
+
+    sessions.insert_or_update_timer(1);
+    clock.advance(Duration::from_secs(2));
+    assert_eq!(sessions.time_remaining(&1), Some(Duration::from_secs(8)));
+    assert_eq!(
+        sessions.last_seen(&1),
+        Some(clock.now() - Duration::from_secs(2))
+    );
+    assert_eq!(sessions.age(&1), Some(Duration::from_secs(5)));
+
+    assert_eq!(sessions.time_remaining(&2), None);
+    assert_eq!(sessions.last_seen(&2), None);
+    assert_eq!(sessions.age(&2), None);
+}
+
+#[test]
+fn bulk_inspection() {
+    let clock = ManualClock::new();
+    let users = SandClock::<u32>::new(SandClockConfig::default())
+        .set_time_out_event(|_| {})
+        .set_time_out_duration(Duration::from_secs(10))
+        .set_clock(clock.clone())
+        .build()
+        .unwrap();
+
+    for user in 0..5 {
+        users.insert_or_update_timer(user);
+        clock.advance(Duration::from_secs(1));
+    }
+    let mut keys: Vec<_> = users.keys().collect();
+    keys.sort_unstable();
+    assert_eq!(keys, [0, 1, 2, 3, 4]);
+
+    // the entries can be inspected while the clock is used.
+    for (user, info) in users.iter() {
+        assert_eq!(info.heartbeats, 1);
+        assert_eq!(users.last_seen(&user), Some(info.last_seen));
+        users.insert_or_update_timer(user + 10);
+    }
+    assert_eq!(users.get_entries_count(), 10);
+
+    // keys 0 and 1 are due within 6 seconds, the soonest first.
+    let expiring: Vec<_> = users
+        .expiring_within(Duration::from_secs(6))
+        .map(|(user, _)| user)
+        .collect();
+    assert_eq!(expiring, [0, 1]);
+
+    // a key without deadline comes after all the keys that are due.
+    users.insert_or_update_timer_with_timeout(20, Duration::MAX);
+    let expiring: Vec<_> = users
+        .expiring_within(Duration::MAX)
+        .map(|(user, _)| user)
+        .collect();
+    assert_eq!(expiring.len(), 11);
+    assert_eq!(expiring[..5], [0, 1, 2, 3, 4]);
+    assert_eq!(expiring.last(), Some(&20));
+
+    users.retain(|user, _| *user < 10);
+    assert_eq!(users.get_entries_count(), 5);
+    users.retain(|_, info| info.time_remaining(clock.now()) > Duration::from_secs(7));
+    let mut keys: Vec<_> = users.keys().collect();
+    keys.sort_unstable();
+    assert_eq!(keys, [3, 4]);
+}
+
+#[test]
+fn touch_and_insert_if_absent() {
+    let clock = ManualClock::new();
+    let (devices, events) = SandClock::<u32>::new(SandClockConfig::default())
+        .set_time_out_duration(Duration::from_secs(10))
+        .set_clock(clock.clone())
+        .build_with_receiver()
+        .unwrap();
+
+    assert!(!devices.touch(&1));
+    assert!(!devices.contains_key(1));
+
+    assert!(devices.insert_if_absent(1));
+    assert!(!devices.insert_if_absent(1));
+    assert_eq!(devices.get_entries_count(), 1);
+
+    clock.advance(Duration::from_secs(6));
+    // insert_if_absent leaves the timer of a tracked key untouched, touch refreshes it.
+    assert!(!devices.insert_if_absent(1));
+    assert_eq!(devices.time_remaining(&1), Some(Duration::from_secs(4)));
+    assert!(devices.touch(&1));
+    assert_eq!(devices.time_remaining(&1), Some(Duration::from_secs(10)));
+
+    // a late signal does not bring an expired key back.
+    clock.advance(Duration::from_secs(10));
+    assert!(matches!(
+        events.try_recv(),
+        Ok(ClockEvent::TimeOut(TimeOutInfo { key: 1, .. }, _))
+    ));
+    assert!(!devices.touch(&1));
+    assert!(!devices.contains_key(1));
+    assert!(devices.insert_if_absent(1));
+    assert_eq!(devices.get_entries_count(), 1);
+}
+
+#[test]
+fn lifecycle_events() {
+    let clock = ManualClock::new();
+    let (presence, events) = SandClock::<u32>::new(SandClockConfig::default())
+        .set_lifecycle_events()
+        .set_refreshed_events(2)
+        .set_time_out_duration(Duration::from_secs(10))
+        .set_clock(clock.clone())
+        .build_with_receiver()
+        .unwrap();
+    let lifecycle = |events: &EventReceiver<u32>| -> Vec<String> {
+        events
+            .try_iter()
+            .map(|clock_event| match clock_event {
+                ClockEvent::Inserted(key) => format!("inserted {key}"),
+                ClockEvent::Refreshed(key) => format!("refreshed {key}"),
+                ClockEvent::Removed(key) => format!("removed {key}"),
+                ClockEvent::TimeOut(TimeOutInfo { key, .. }, _) => format!("timeout {key}"),
+                other => format!("{other}"),
+            })
+            .collect()
+    };
+
+    presence.insert_or_update_timer(1);
+    presence.insert_if_absent(2);
+    presence.insert_or_update_timer_with_timeout(3, Duration::from_secs(5));
+    assert_eq!(
+        lifecycle(&events),
+        ["inserted 1", "inserted 2", "inserted 3"]
+    );
+
+    // one signal in two is reported.
+    for _ in 0..4 {
+        presence.insert_or_update_timer(1);
+    }
+    assert!(presence.touch(&2));
+    assert_eq!(lifecycle(&events), ["refreshed 1", "refreshed 1"]);
+
+    presence.remove_key(1);
+    presence.remove_key(1);
+    presence.retain(|key, _| *key != 2);
+    clock.advance(Duration::from_secs(5));
+    presence.insert_or_update_timer(3);
+    assert_eq!(
+        lifecycle(&events),
+        ["removed 1", "removed 2", "timeout 3", "inserted 3"]
+    );
+}
+
+#[test]
+fn expiry_races_with_signals() {
+    let time_out_duration = Duration::from_millis(3);
+    let clock = ManualClock::new();
+    let (presence, events) = SandClock::<u32>::new(SandClockConfig::default())
+        .set_lifecycle_events()
+        .set_time_out_duration(time_out_duration)
+        .set_clock(clock.clone())
+        .build_with_receiver()
+        .unwrap();
+
+    let running = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(true));
+    let signalers: Vec<_> = (0..4u32)
+        .map(|seed| {
+            let presence = presence.clone();
+            let running = running.clone();
+            std::thread::spawn(move || {
+                let mut state = seed + 1;
+                while running.load(std::sync::atomic::Ordering::Relaxed) {
+                    // xorshift, so that each key is signaled at an irregular pace.
+                    state ^= state << 13;
+                    state ^= state >> 17;
+                    state ^= state << 5;
+                    presence.insert_or_update_timer(state % 64);
+                    std::thread::yield_now();
+                }
+            })
+        })
+        .collect();
+    for _ in 0..20_000 {
+        clock.advance(Duration::from_millis(1));
+        std::thread::yield_now();
+    }
+    running.store(false, std::sync::atomic::Ordering::Relaxed);
+    for signaler in signalers {
+        signaler.join().unwrap();
+    }
+
+    // each key alternates between insertion and timeout, and only times out once silent.
+    let mut tracked = std::collections::HashSet::new();
+    for clock_event in events.try_iter() {
+        match clock_event {
+            ClockEvent::Inserted(key) => assert!(tracked.insert(key), "{key} inserted twice"),
+            ClockEvent::TimeOut(info, _) => {
+                assert!(tracked.remove(&info.key), "{} timed out twice", info.key);
+                assert!(info.expired_at >= info.last_seen + time_out_duration);
+            }
+            other => panic!("unexpected event {other}"),
+        }
+    }
+    assert_eq!(tracked.len(), presence.get_entries_count());
+    assert!(tracked.iter().all(|key| presence.contains_key(*key)));
+}
+
+#[test]
+fn timeout_precedes_reinsertion() {
+    use std::sync::{Arc, Barrier};
+
+    let expiring = Arc::new(Barrier::new(2));
+    let clock = ManualClock::new();
+    let (presence, events) = SandClock::<u32>::new(SandClockConfig::default())
+        .set_lifecycle_events()
+        .set_time_out_duration(Duration::from_secs(1))
+        .set_clock(clock.clone())
+        .build_with_receiver()
+        .unwrap();
+    // holds the timer loop inside the expiry of the key, its entry locked, until the key is
+    // signaled again.
+    presence.set_expiry_hook({
+        let expiring = expiring.clone();
+        move || {
+            expiring.wait();
+            std::thread::sleep(Duration::from_millis(50));
+        }
+    });
+    presence.insert_or_update_timer(1);
+
+    let timer_loop = std::thread::spawn({
+        let clock = clock.clone();
+        move || clock.advance(Duration::from_secs(2))
+    });
+    // the key is signaled while the timer loop is expiring it.
+    expiring.wait();
+    presence.insert_or_update_timer(1);
+    timer_loop.join().unwrap();
+
+    // the signal waits for the end of the expiry, and inserts the key again after its timeout.
+    let events: Vec<_> = events
+        .try_iter()
+        .map(|clock_event| match clock_event {
+            ClockEvent::Inserted(key) | ClockEvent::TimeOut(TimeOutInfo { key, .. }, _) => {
+                (clock_event.kind(), key)
+            }
+            other => panic!("unexpected event {other}"),
+        })
+        .collect();
+    assert_eq!(events, [("inserted", 1), ("timeout", 1), ("inserted", 1)]);
+    assert!(presence.contains_key(1));
+}
+
+#[test]
+fn stats() {
+    let clock = ManualClock::new();
+    let (presence, events) = SandClock::<u32>::new(SandClockConfig::default())
+        .set_time_out_duration(Duration::from_secs(10))
+        .set_clock(clock.clone())
+        .build_with_receiver()
+        .unwrap();
+    assert_eq!(presence.stats(), SandClockStats::default());
+
+    for key in 0..6 {
+        presence.insert_or_update_timer(key);
+    }
+    presence.insert_or_update_timer(0);
+    assert!(presence.touch(&1));
+    presence.remove_key(2);
+    presence.remove_key(2);
+    presence.retain(|key, _| *key != 3);
+    clock.advance(Duration::from_secs(5));
+    presence.insert_or_update_timer(0);
+    clock.advance(Duration::from_secs(5));
+
+    // keys 1, 4 and 5 timed out, once each.
+    let stats = presence.stats();
+    assert_eq!(stats.entries, 1);
+    assert_eq!(stats.entries, presence.get_entries_count());
+    assert_eq!(
+        (
+            stats.inserts,
+            stats.refreshes,
+            stats.timeouts,
+            stats.removals
+        ),
+        (6, 3, 3, 2)
+    );
+    assert_eq!(stats.queued_events, 3);
+    assert_eq!(events.try_iter().count(), 3);
+    assert_eq!(presence.stats().queued_events, 0);
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn metrics() {
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    let clock = ManualClock::new();
+    let (sessions, _events) = metrics::with_local_recorder(&recorder, || {
+        SandClock::<u32>::new(SandClockConfig::new().name("sessions"))
+            .set_time_out_duration(Duration::from_secs(10))
+            .set_clock(clock.clone())
+            .build_with_receiver()
+            .unwrap()
+    });
+
+    for key in 0..3 {
+        sessions.insert_or_update_timer(key);
+    }
+    sessions.insert_or_update_timer(0);
+    sessions.remove_key(1);
+    clock.advance(Duration::from_secs(12));
+
+    let metrics: std::collections::HashMap<_, _> = snapshotter
+        .snapshot()
+        .into_vec()
+        .into_iter()
+        .map(|(key, _, _, value)| {
+            let key = key.key();
+            assert!(
+                key.labels()
+                    .any(|label| label.key() == "clock" && label.value() == "sessions")
+            );
+            (key.name().to_string(), value)
+        })
+        .collect();
+    let gauge = |value: f64| DebugValue::Gauge(value.into());
+    assert_eq!(metrics["sand_clock_entries"], gauge(0.0));
+    assert_eq!(metrics["sand_clock_inserts_total"], DebugValue::Counter(3));
+    assert_eq!(
+        metrics["sand_clock_refreshes_total"],
+        DebugValue::Counter(1)
+    );
+    assert_eq!(metrics["sand_clock_timeouts_total"], DebugValue::Counter(2));
+    assert_eq!(metrics["sand_clock_removals_total"], DebugValue::Counter(1));
+    assert_eq!(metrics["sand_clock_queued_events"], gauge(2.0));
+    assert!(
+        matches!(&metrics["sand_clock_tick_duration_seconds"], DebugValue::Histogram(ticks) if ticks.len() == 1)
+    );
+    // both keys were due 2 seconds before the tick.
+    assert_eq!(
+        metrics["sand_clock_expiry_lateness_seconds"],
+        DebugValue::Histogram(vec![2.0.into(), 2.0.into()])
+    );
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn tracing_spans() {
+    use std::sync::{Arc, Mutex};
+    use tracing_subscriber::fmt::{format::FmtSpan, writer::MakeWriter};
+
+    #[derive(Clone, Default)]
+    struct Output(Arc<Mutex<Vec<u8>>>);
+    impl std::io::Write for Output {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    impl<'a> MakeWriter<'a> for Output {
+        type Writer = Self;
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    let output = Output::default();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::DEBUG)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_ansi(false)
+        .with_writer(output.clone())
+        .finish();
+    // with a manual clock and an inline executor, ticks and callbacks run on this thread.
+    tracing::subscriber::with_default(subscriber, || {
+        let clock = ManualClock::new();
+        let sessions = SandClock::<u32>::new(
+            SandClockConfig::new()
+                .name("sessions")
+                .executor(CallBackExecutor::Inline),
+        )
+        .set_time_out_event(|_| tracing::info!("session closed"))
+        .set_time_out_duration(Duration::from_secs(10))
+        .set_clock(clock.clone())
+        .build()
+        .unwrap();
+        sessions.insert_or_update_timer(1);
+        sessions.insert_or_update_timer(2);
+        clock.advance(Duration::from_secs(10));
+    });
+
+    let output = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+    assert!(
+        output.contains(r#"sand_clock.tick{clock="sessions" scanned=2 expired=2 duration_us="#),
+        "{output}"
+    );
+    assert!(
+        output.contains(r#"sand_clock.callback{clock="sessions" event="timeout"}: sand_clock::test: session closed"#),
+        "{output}"
+    );
+}
+
+#[test]
+fn callback_panics() {
+    for executor in [CallBackExecutor::Threads(2), CallBackExecutor::Inline] {
+        let (delivered_sender, delivered) = crossbeam_channel::unbounded();
+        let (panic_sender, panics) = crossbeam_channel::unbounded();
+        let clock = ManualClock::new();
+        let sessions = SandClock::<u32>::new(SandClockConfig::new().executor(executor))
+            .set_time_out_event(move |clock_event| {
+                if let ClockEvent::TimeOut(TimeOutInfo { key, .. }, _) = clock_event {
+                    assert!(key != 1, "session {key} is broken");
+                    delivered_sender.send(key).unwrap();
+                }
+            })
+            .on_callback_error(move |call_back_panic| {
+                panic_sender.send(call_back_panic).unwrap();
+                panic!("the error hook is broken too");
+            })
+            .set_time_out_duration(Duration::from_secs(10))
+            .set_clock(clock.clone())
+            .build()
+            .unwrap();
+
+        sessions.insert_or_update_timer(1);
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(
+            panics.recv_timeout(Duration::from_secs(1)),
+            Ok(CallBackPanic {
+                event: "timeout",
+                message: Some("session 1 is broken".to_string()),
+            })
+        );
+
+        // the dispatcher survived the panics of the callback and of its hook.
+        sessions.insert_or_update_timer(2);
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(delivered.recv_timeout(Duration::from_secs(1)), Ok(2));
+        assert_eq!(sessions.stats().callback_panics, 1);
+    }
+}
@@ -1,144 +1,734 @@
+use crossbeam_channel::{Receiver, RecvTimeoutError, SendError, Sender};
 use dashmap::DashMap;
 use log::info;
 use rayon::ThreadPoolBuilder;
 
 use crate::{
     InsertSync, SandClockInsertion,
-    config::SandClockConfig,
-    user_table::{ClockEvent, ClockEventIntern, TimerStatus},
+    clock::{Clock, Tick},
+    config::{PollingMode, SandClockConfig, ShutdownPolicy},
+    executor::CallBackExecutor,
+    scheduler::DeadlineQueue,
+    stats::Counters,
+    user_table::{ClockEvent, EventReceiver, Slot, TimeOutCallBack, TimeOutInfo},
 };
 use std::{
     fmt::Debug,
     sync::{
-        Arc,
+        Arc, Mutex, OnceLock,
         atomic::{AtomicBool, AtomicUsize},
     },
-    time::{Duration, Instant},
+    thread::JoinHandle,
+    time::Instant,
 };
 
-#[allow(dead_code)]
-pub struct TimerLoop<K: SandClockInsertion + Debug> {
-    t_o_cb: Arc<dyn Fn(ClockEvent<K>) + Send + Sync + 'static>,
-    map: Arc<DashMap<InsertSync<K>, TimerStatus>>,
+/// How the events of a [`SandClock`](crate::SandClock) reach the user.
+pub enum EventDelivery<K: SandClockInsertion, V> {
+    /// Each event is passed to the callback, on the executor of the [`SandClockConfig`].
+    CallBack(TimeOutCallBack<K, V>),
+    /// Each event is sent into a bounded stream, waiting for room when it is full. The
+    /// events waiting meanwhile stay in the unbounded job channel.
+    #[cfg(feature = "stream")]
+    Stream(futures::channel::mpsc::Sender<ClockEvent<K, V>>),
+    /// Events are left in the job channel, whose receiver is handed to the user.
+    Channel,
 }
 
-impl<K: SandClockInsertion + Debug> TimerLoop<K> {
+/// State of the polling loop of a [`SandClock`](crate::SandClock).
+///
+/// Each [`Self::tick()`] pops the keys that are due from the shared [`DeadlineQueue`],
+/// checks them against their [`TimerStatus`], and sends a timeout job for the expired ones.
+pub struct TimerLoop<K: SandClockInsertion, V: Send + Sync + 'static = ()> {
+    map: Arc<DashMap<InsertSync<K>, Slot<V>>>,
+    queue: Arc<Mutex<DeadlineQueue<InsertSync<K>>>>,
+    counters: Arc<Counters>,
+    events: EventSender<K, V>,
+    reschedule_queue: Vec<(InsertSync<K>, Instant)>,
+    closed: bool,
+    #[cfg(feature = "tracing")]
+    name: Arc<str>,
+    #[cfg(test)]
+    expiry_hook: Option<ExpiryHook>,
+}
+
+/// Called by the loop while the entry of an expired key is locked, before its `TimeOut` is
+/// sent, to force interleavings in tests.
+#[cfg(test)]
+pub(crate) type ExpiryHook = Arc<dyn Fn() + Send + Sync + 'static>;
+
+impl<K: SandClockInsertion, V: Send + Sync + 'static> TimerLoop<K, V> {
+    /// Creates the loop state over a map and its deadline queue.
+    ///
+    /// Timeout jobs produced by [`Self::tick()`] are sent to `job_sender`. With the `tracing`
+    /// feature, the spans of the loop carry the clock `name`.
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    pub fn new(
+        name: &str,
+        map: &Arc<DashMap<InsertSync<K>, Slot<V>>>,
+        queue: &Arc<Mutex<DeadlineQueue<InsertSync<K>>>>,
+        counters: &Arc<Counters>,
+        job_sender: Sender<ClockEvent<K, V>>,
+    ) -> Self {
+        Self {
+            map: map.clone(),
+            queue: queue.clone(),
+            counters: counters.clone(),
+            events: EventSender::new(job_sender),
+            reschedule_queue: vec![],
+            closed: false,
+            #[cfg(feature = "tracing")]
+            name: name.into(),
+            #[cfg(test)]
+            expiry_hook: None,
+        }
+    }
+    /// Returns the sender of the events of the loop, to send events from the `SandClock`.
+    #[must_use]
+    pub fn event_sender(&self) -> EventSender<K, V> {
+        self.events.clone()
+    }
+    /// Runs one polling cycle at `now` and returns the number of expired keys.
+    ///
+    /// Only the keys whose scheduled deadline is reached are looked up in the map. A key that
+    /// has been refreshed since it was scheduled is pushed back with its new deadline.
+    pub fn tick(&mut self, now: Instant) -> usize {
+        // measured on the wall clock, whatever the clock of the loop.
+        let started = Instant::now();
+        #[cfg(feature = "tracing")]
+        let span = crate::trace::tick_span(&self.name);
+        #[cfg(feature = "tracing")]
+        let _entered = span.enter();
+        let mut due = vec![];
+        if let Ok(mut queue) = self.queue.lock() {
+            while let Some(scheduled) = queue.pop_due(now) {
+                due.push(scheduled);
+            }
+        }
+
+        #[cfg(feature = "tracing")]
+        let scanned = due.len();
+        let mut removables = 0;
+        for (key, scheduled_deadline) in due {
+            let dashmap::Entry::Occupied(mut entry) = self.map.entry(key) else {
+                // removed since it was scheduled.
+                continue;
+            };
+            let (connection_status, _) = entry.get_mut();
+
+            // a more recent schedule of this key exists, this one is stale.
+            if connection_status.scheduled() != Some(scheduled_deadline) {
+                continue;
+            }
+            if connection_status.is_timed_out(now) {
+                // detected, reported and removed under the same lock: a signal either lands
+                // before and keeps the key alive, or after the `TimeOut` and inserts it again.
+                connection_status.expired();
+                removables += 1;
+                let key = entry.key().clone().into_inner();
+                let time_out_info = TimeOutInfo::new(key, &entry.get().0, now);
+                // the count is updated before the callback can observe the removal.
+                self.counters.timed_out(time_out_info.lateness());
+                #[cfg(test)]
+                if let Some(expiry_hook) = &self.expiry_hook {
+                    (**expiry_hook)();
+                }
+                // the value is only taken out of a slot that is removed under this lock.
+                if let Some(value) = entry.get_mut().1.take()
+                    && let Err(e) = self.events.send(ClockEvent::TimeOut(time_out_info, value))
+                {
+                    info!("failed to externalize the expired key [{e:?}]");
+                }
+                entry.remove();
+                continue;
+            }
+            if connection_status.becomes_suspect(now) {
+                connection_status.suspect();
+                // sent under the lock of the key, so a `Recovered` cannot come first.
+                if let Err(e) = self
+                    .events
+                    .send(ClockEvent::Suspect(entry.key().clone().into_inner()))
+                {
+                    info!("failed to externalize the suspect key [{e:?}]");
+                }
+            }
+            if let Some(deadline) = entry.get_mut().0.schedule() {
+                let key = entry.key().clone();
+                drop(entry);
+                self.reschedule_queue.push((key, deadline));
+            }
+        }
+
+        if !self.reschedule_queue.is_empty()
+            && let Ok(mut queue) = self.queue.lock()
+        {
+            for (key, deadline) in self.reschedule_queue.drain(..) {
+                queue.push(key, deadline);
+            }
+        }
+        self.compact();
+        let duration = started.elapsed();
+        self.counters.ticked(duration, self.events.queued());
+        #[cfg(feature = "tracing")]
+        crate::trace::record_tick(&span, scanned, removables, duration.as_micros());
+        removables
+    }
+    /// Drops the stale entries of the deadline queue once they outnumber the tracked keys.
+    ///
+    /// The entries are taken out of the queue while their keys are looked up, so the map
+    /// shards and the queue are still never locked together.
+    fn compact(&mut self) {
+        let tracked = self.map.len();
+        let Some(mut scheduled) = self
+            .queue
+            .lock()
+            .ok()
+            .filter(|queue| queue.len() > 2 * tracked)
+            .map(|mut queue| std::mem::take(&mut *queue))
+        else {
+            return;
+        };
+        scheduled.retain(|key, deadline| {
+            self.map
+                .get(key)
+                .is_some_and(|entry| entry.0.scheduled() == Some(deadline))
+        });
+        if let Ok(mut queue) = self.queue.lock() {
+            queue.append(&mut scheduled);
+        }
+    }
+    /// Sends [`ClockEvent::SandClockDrop`], the last event of the loop. Later calls do nothing.
+    pub fn close(&mut self) {
+        if self.closed {
+            return;
+        }
+        self.closed = true;
+        // Send a Close event to the time_out callback.
+        if let Err(e) = self.events.send(ClockEvent::SandClockDrop) {
+            info!("Failed to Send Drop Signal to user [{e:?}]");
+        }
+    }
     ///
     ////// Starts the internal timer loop in a dedicated background thread.
     ///
-    /// This loop periodically scans all registered entries in the `SandClock`.
+    /// This loop periodically pops the entries of the `SandClock` whose deadline is reached.
     /// For each entry, it compares the current time (`Instant::now()`) with the last recorded update time.
-    /// If the elapsed duration exceeds the user-defined timeout threshold,
+    /// If the elapsed duration exceeds the timeout duration stored in the entry's [`TimerStatus`],
     /// the corresponding timeout event callback is triggered.
     ///
     /// The refresh interval of the loop is configured via the provided [`SandClockConfig`].
     ///
-    /// This method is called internally by [`SandClock::build()`] and should not need
+    /// This method is called internally by [`SandClockBuilder::build()`] and should not need
     /// to be invoked manually under normal usage.
     ///
     /// # Arguments
     /// - `config`: The configuration object that sets the refresh interval of the loop.
     /// - `map`: Shared concurrent map storing entries and their timeout info.
-    /// - `t_o_cb`: User-defined callback triggered on timeout.
-    /// - `time_out`: The duration threshold beyond which a key is considered inactive.
+    /// - `queue`: Deadline queue shared with the `SandClock`, fed on insertion.
+    /// - `clock`: Source of time of the loop, which may also drive it instead of the polling thread.
+    /// - `event_delivery`: User-defined callback triggered on timeout, or the stream the events are sent to.
+    ///
+    /// Returns the [`LoopHandle`] that stops the loop, and the receiver of the events in
+    /// channel mode.
     ///
     /// # Note
-    /// Expired entries are removed after each polling cycle to free resources.
+    /// Expired entries are removed at the end of each tick, and their key and value are
+    /// handed to the callback.
+    ///
+    /// [`SandClockBuilder::build()`]: crate::SandClockBuilder::build
     pub fn run(
         config: &SandClockConfig,
-        counter: &Arc<AtomicUsize>,
-        map: &Arc<DashMap<InsertSync<K>, TimerStatus>>,
-        t_o_cb: &Arc<dyn Fn(ClockEvent<K>) + Send + Sync + 'static>,
-        time_out: Duration,
-        closing_trigger: &Arc<AtomicBool>,
-    ) {
-        let _timer_loop: TimerLoop<K> = TimerLoop {
-            t_o_cb: t_o_cb.clone(),
-            map: map.clone(),
+        counters: &Arc<Counters>,
+        map: &Arc<DashMap<InsertSync<K>, Slot<V>>>,
+        queue: &Arc<Mutex<DeadlineQueue<InsertSync<K>>>>,
+        clock: &Arc<dyn Clock>,
+        event_delivery: EventDelivery<K, V>,
+    ) -> (LoopHandle<K, V>, Option<EventReceiver<K, V>>) {
+        let (job_sender, job_receiver) = crossbeam_channel::unbounded::<ClockEvent<K, V>>();
+        let (wake_sender, wake_receiver) = crossbeam_channel::bounded::<()>(1);
+        let closing_trigger = Arc::new(AtomicBool::new(false));
+        let cancel_pending = Arc::new(AtomicBool::new(false));
+
+        #[cfg(feature = "tracing")]
+        let event_delivery = match event_delivery {
+            EventDelivery::CallBack(call_back) => EventDelivery::CallBack(
+                crate::trace::traced_call_back(call_back, config.get_name()),
+            ),
+            event_delivery => event_delivery,
+        };
+        let executor = config.get_executor().clone();
+        let driven: Arc<OnceLock<Arc<DrivenDispatch<K, V>>>> = Arc::default();
+        let (user_receiver, dispatched) = match event_delivery {
+            // In channel mode, the job channel is handed to the user: no dispatch thread.
+            EventDelivery::Channel => (Some(job_receiver), None),
+            // Inline, the polling thread drains the job channel itself.
+            EventDelivery::CallBack(call_back) if matches!(executor, CallBackExecutor::Inline) => {
+                let _ = driven.set(DrivenDispatch::new(
+                    call_back,
+                    executor.clone(),
+                    job_receiver,
+                    &cancel_pending,
+                ));
+                (None, None)
+            }
+            event_delivery => (None, Some((event_delivery, job_receiver))),
         };
-        let map = map.clone();
-        let t_o_cb = t_o_cb.clone();
 
-        let (job_sender, job_receiver) =
-            crossbeam_channel::unbounded::<ClockEventIntern<InsertSync<K>>>();
+        let refresh_duration = config.get_timer_loop_refreshing_duration();
+        let timer_loop = TimerLoop::new(config.get_name(), map, queue, counters, job_sender);
+        let events = timer_loop.event_sender();
+        let timer_loop = Arc::new(Mutex::new(timer_loop));
 
-        let counter = counter.clone();
         let closing_trigger_0 = closing_trigger.clone();
-        std::thread::spawn(move || {
-            if let Ok(thread_pool) = ThreadPoolBuilder::new().num_threads(4).build() {
-                while let Ok(key) = job_receiver.recv() {
-                    let close = thread_pool.install(|| match key {
-                        ClockEventIntern::TimeOutIntern(key) => {
-                            (*t_o_cb)(ClockEvent::TimeOut(key.into_inner()));
-                            false
-                        }
-                        ClockEventIntern::SandClockDrop => {
-                            (*t_o_cb)(ClockEvent::SandClockDrop);
-                            true
+        let timer_loop_0 = timer_loop.clone();
+        let driven_0 = driven.clone();
+        let closing_tick: Tick = Box::new(move |now| {
+            let Ok(mut timer_loop) = timer_loop_0.lock() else {
+                return false;
+            };
+            // stops the loops, expires the thread once closed.
+            let running = if closing_trigger_0.load(std::sync::atomic::Ordering::Relaxed) {
+                timer_loop.close();
+                false
+            } else {
+                timer_loop.tick(now);
+                true
+            };
+            drop(timer_loop);
+            if let Some(driven) = driven_0.get() {
+                driven.drain();
+            }
+            running
+        });
+
+        // a clock that drives the loop itself (e.g. a `ManualClock`) needs no polling thread.
+        let adaptive = config.get_polling_mode() == PollingMode::Adaptive;
+        let polling_thread = match clock.drive(closing_tick) {
+            Ok(()) => None,
+            Err(mut tick) => {
+                let clock = clock.clone();
+                let queue = queue.clone();
+                Some(std::thread::spawn(move || {
+                    while tick(clock.now()) {
+                        let wait = if adaptive {
+                            // parks until the earliest deadline, or until woken up.
+                            queue
+                                .lock()
+                                .ok()
+                                .and_then(|queue| queue.next_deadline())
+                                .map(|deadline| deadline.saturating_duration_since(clock.now()))
+                        } else {
+                            Some(refresh_duration)
+                        };
+                        // woken up early when the clock is dropped or shut down.
+                        let disconnected = match wait {
+                            Some(wait) => matches!(
+                                wake_receiver.recv_timeout(wait),
+                                Err(RecvTimeoutError::Disconnected)
+                            ),
+                            None => wake_receiver.recv().is_err(),
+                        };
+                        if disconnected {
+                            std::thread::sleep(refresh_duration);
                         }
-                    });
-                    if close {
-                        break;
                     }
+                }))
+            }
+        };
+        let dispatch_thread = dispatched.and_then(|(event_delivery, job_receiver)| {
+            match event_delivery {
+                // a loop driven by its clock (e.g. a `TimerDriver`) hands its jobs to a shared
+                // executor after each tick, without a dispatch thread of its own.
+                EventDelivery::CallBack(call_back)
+                    if polling_thread.is_none()
+                        && matches!(
+                            executor,
+                            CallBackExecutor::Pool(_) | CallBackExecutor::Custom(_)
+                        ) =>
+                {
+                    let _ = driven.set(DrivenDispatch::new(
+                        call_back,
+                        executor,
+                        job_receiver,
+                        &cancel_pending,
+                    ));
+                    None
+                }
+                event_delivery => {
+                    let cancel_pending = cancel_pending.clone();
+                    Some(std::thread::spawn(move || {
+                        Self::dispatch(event_delivery, &executor, &job_receiver, &cancel_pending)
+                    }))
                 }
             }
         });
+        (
+            LoopHandle {
+                adaptive: adaptive && polling_thread.is_some(),
+                timer_loop,
+                events,
+                closing_trigger,
+                cancel_pending,
+                wake_sender,
+                driven,
+                polling_thread,
+                dispatch_thread,
+            },
+            user_receiver,
+        )
+    }
+    /// Body of the dispatch thread: hands each job of the channel to the user, until
+    /// [`ClockEvent::SandClockDrop`].
+    fn dispatch(
+        event_delivery: EventDelivery<K, V>,
+        executor: &CallBackExecutor,
+        job_receiver: &Receiver<ClockEvent<K, V>>,
+        cancel_pending: &AtomicBool,
+    ) -> ShutdownReport {
+        match event_delivery {
+            EventDelivery::CallBack(t_o_cb) => match executor {
+                CallBackExecutor::Threads(num_threads) => {
+                    if let Ok(thread_pool) =
+                        ThreadPoolBuilder::new().num_threads(*num_threads).build()
+                    {
+                        Self::dispatch_with(job_receiver, cancel_pending, |clock_event| {
+                            thread_pool.install(|| (*t_o_cb)(clock_event));
+                        })
+                    } else {
+                        ShutdownReport::default()
+                    }
+                }
+                CallBackExecutor::Pool(thread_pool) => {
+                    Self::dispatch_with(job_receiver, cancel_pending, |clock_event| {
+                        thread_pool.install(|| (*t_o_cb)(clock_event));
+                    })
+                }
+                CallBackExecutor::Custom(executor) => {
+                    Self::dispatch_with(job_receiver, cancel_pending, |clock_event| {
+                        let (done_sender, done_receiver) = crossbeam_channel::bounded::<()>(1);
+                        let t_o_cb = t_o_cb.clone();
+                        executor.execute(Box::new(move || {
+                            (*t_o_cb)(clock_event);
+                            let _ = done_sender.send(());
+                        }));
+                        // disconnected as well if the executor drops the job.
+                        let _ = done_receiver.recv();
+                    })
+                }
+                // run on the polling thread, see `InlineDispatch`.
+                CallBackExecutor::Inline => ShutdownReport::default(),
+            },
+            #[cfg(feature = "stream")]
+            EventDelivery::Stream(mut sender) => {
+                use futures::SinkExt;
 
-        let refresh_duration = config.get_timer_loop_refreshing_duration();
+                Self::dispatch_with(job_receiver, cancel_pending, |clock_event| {
+                    // waits for room in the stream, while the next events queue up in the
+                    // job channel.
+                    if let Err(e) = futures::executor::block_on(sender.send(clock_event)) {
+                        info!("Failed to send the clock event into the stream [{e:?}]");
+                    }
+                })
+            }
+            EventDelivery::Channel => ShutdownReport::default(),
+        }
+    }
+    fn dispatch_with(
+        job_receiver: &Receiver<ClockEvent<K, V>>,
+        cancel_pending: &AtomicBool,
+        mut deliver: impl FnMut(ClockEvent<K, V>),
+    ) -> ShutdownReport {
+        let mut report = ShutdownReport::default();
+        while let Ok(clock_event) = job_receiver.recv() {
+            if let ClockEvent::SandClockDrop = clock_event {
+                deliver(clock_event);
+                break;
+            }
+            if cancel_pending.load(std::sync::atomic::Ordering::Relaxed) {
+                report.dropped += 1;
+                continue;
+            }
+            deliver(clock_event);
+            report.delivered += 1;
+        }
+        report
+    }
+}
 
-        std::thread::spawn(move || {
-            let mut expired_queue: Vec<InsertSync<K>> = vec![];
+/// Sending side of the job channel, shared by the loop and its `SandClock`.
+pub struct EventSender<K: SandClockInsertion, V> {
+    job_sender: Sender<ClockEvent<K, V>>,
+    sent: Arc<AtomicUsize>,
+}
 
-            'outter: loop {
-                if closing_trigger_0.load(std::sync::atomic::Ordering::Relaxed) {
-                    // Send a Close event to the time_out callback.
-                    if let Err(e) = job_sender.send(ClockEventIntern::SandClockDrop) {
-                        info!("Failed to Send Drop Signal to user [{e:?}]");
-                    }
-                    // stops the loops, expires the thread.
-                    break 'outter;
-                }
-                let mut conn_it = map.iter_mut();
+impl<K: SandClockInsertion, V> Clone for EventSender<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            job_sender: self.job_sender.clone(),
+            sent: self.sent.clone(),
+        }
+    }
+}
 
-                let now = Instant::now();
+impl<K: SandClockInsertion, V> EventSender<K, V> {
+    fn new(job_sender: Sender<ClockEvent<K, V>>) -> Self {
+        Self {
+            job_sender,
+            sent: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+    /// Sends an event to the dispatch thread, or to the user in channel mode.
+    ///
+    /// # Errors
+    /// Returns the event back if the receiving side is gone.
+    pub fn send(&self, clock_event: ClockEvent<K, V>) -> Result<(), SendError<ClockEvent<K, V>>> {
+        let counted = !matches!(clock_event, ClockEvent::SandClockDrop);
+        // counted before it can be received, so that `sent` never lags behind the receiver.
+        if counted {
+            self.sent.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        }
+        self.job_sender.send(clock_event).inspect_err(|_| {
+            if counted {
+                self.sent.fetch_sub(1, std::sync::atomic::Ordering::Relaxed);
+            }
+        })
+    }
+    /// Returns the number of events sent but not yet received.
+    #[must_use]
+    pub fn queued(&self) -> usize {
+        self.job_sender.len()
+    }
+    /// Returns the number of events sent, [`ClockEvent::SandClockDrop`] excluded.
+    #[must_use]
+    pub fn sent(&self) -> usize {
+        self.sent.load(std::sync::atomic::Ordering::Relaxed)
+    }
+}
 
-                'inner_it: loop {
-                    if let Some(mut connection_status_ref) = conn_it.next() {
-                        let connection_status = connection_status_ref.value();
+/// Events counted by [`SandClock::shutdown()`](crate::SandClock::shutdown).
+///
+/// The final [`ClockEvent::SandClockDrop`] is not counted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ShutdownReport {
+    /// Events handed to the user over the lifetime of the clock.
+    pub delivered: usize,
+    /// Pending events discarded by a [`ShutdownPolicy::Cancel`] shutdown.
+    pub dropped: usize,
+}
 
-                        if connection_status.is_expired() {
-                            continue 'inner_it;
-                        }
-                        let last_updated_instant =
-                            connection_status.time_out_info().get_last_instant_update();
-
-                        if now.duration_since(last_updated_instant) >= time_out {
-                            let key = connection_status_ref.key().clone();
-
-                            if let Err(e) =
-                                job_sender.send(ClockEventIntern::TimeOutIntern(key.clone()))
-                            {
-                                info!("failed to externalize the expired key [{e:?}]");
-                            }
-                            connection_status_ref.value_mut().expired();
-
-                            // store expired keys in queue and clean the map later.
-                            if !expired_queue.contains(&key) {
-                                expired_queue.push(key);
-                            }
-                        }
-                    } else {
-                        break 'inner_it;
-                    }
+/// Dispatch of a loop without a dispatch thread, run after each of its ticks.
+///
+/// With [`CallBackExecutor::Inline`], the callback runs on the thread of the tick. With a
+/// shared [`CallBackExecutor::Pool`] or [`CallBackExecutor::Custom`] executor, the pending
+/// jobs are handed to it as one task that delivers them in order, and at most one such task
+/// of the loop is queued or running at a time.
+struct DrivenDispatch<K: SandClockInsertion, V> {
+    call_back: TimeOutCallBack<K, V>,
+    executor: CallBackExecutor,
+    job_receiver: Receiver<ClockEvent<K, V>>,
+    cancel_pending: Arc<AtomicBool>,
+    dropped: AtomicUsize,
+    /// A task of the loop is queued or running on the executor.
+    scheduled: AtomicBool,
+    /// Signaled once [`ClockEvent::SandClockDrop`] is delivered.
+    closed: (Sender<()>, Receiver<()>),
+}
+
+impl<K: SandClockInsertion, V> DrivenDispatch<K, V> {
+    fn new(
+        call_back: TimeOutCallBack<K, V>,
+        executor: CallBackExecutor,
+        job_receiver: Receiver<ClockEvent<K, V>>,
+        cancel_pending: &Arc<AtomicBool>,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            call_back,
+            executor,
+            job_receiver,
+            cancel_pending: cancel_pending.clone(),
+            dropped: AtomicUsize::new(0),
+            scheduled: AtomicBool::new(false),
+            closed: crossbeam_channel::bounded(1),
+        })
+    }
+}
+
+impl<K: SandClockInsertion, V: Send + Sync + 'static> DrivenDispatch<K, V> {
+    /// Delivers the jobs sent since the last tick, or hands them to the shared executor.
+    fn drain(self: &Arc<Self>) {
+        match &self.executor {
+            CallBackExecutor::Pool(thread_pool) => {
+                if let Some(task) = self.claim() {
+                    thread_pool.spawn(move || task.run());
                 }
-                std::thread::sleep(refresh_duration);
+            }
+            CallBackExecutor::Custom(executor) => {
+                if let Some(task) = self.claim() {
+                    executor.execute(Box::new(move || task.run()));
+                }
+            }
+            _ => self.deliver_pending(),
+        }
+    }
+    /// Returns a task to hand to the executor, unless one is already queued or running.
+    fn claim(self: &Arc<Self>) -> Option<DrivenTask<K, V>> {
+        (!self
+            .scheduled
+            .swap(true, std::sync::atomic::Ordering::AcqRel))
+        .then(|| DrivenTask(Some(self.clone())))
+    }
+    /// Passes the pending jobs to the callback, or discards them once a
+    /// [`ShutdownPolicy::Cancel`] shutdown has started.
+    fn deliver_pending(&self) {
+        while let Ok(clock_event) = self.job_receiver.try_recv() {
+            if let ClockEvent::SandClockDrop = clock_event {
+                (*self.call_back)(clock_event);
+                let _ = self.closed.0.try_send(());
+                continue;
+            }
+            if self
+                .cancel_pending
+                .load(std::sync::atomic::Ordering::Relaxed)
+            {
+                self.dropped
+                    .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                continue;
+            }
+            (*self.call_back)(clock_event);
+        }
+    }
+    /// Waits for the delivery of [`ClockEvent::SandClockDrop`], after the loop is closed.
+    fn wait_closed(&self) {
+        let _ = self.closed.1.recv();
+    }
+    /// Returns the number of jobs discarded by the shutdown.
+    fn dropped(&self) -> usize {
+        self.dropped.load(std::sync::atomic::Ordering::Relaxed)
+    }
+}
+
+/// The task of a [`DrivenDispatch`] on its shared executor.
+struct DrivenTask<K: SandClockInsertion, V>(Option<Arc<DrivenDispatch<K, V>>>);
+
+impl<K: SandClockInsertion, V: Send + Sync + 'static> DrivenTask<K, V> {
+    fn run(mut self) {
+        let Some(driven) = self.0.take() else {
+            return;
+        };
+        loop {
+            driven.deliver_pending();
+            driven
+                .scheduled
+                .store(false, std::sync::atomic::Ordering::Release);
+            // a job sent after the last receive is delivered here, unless a new task took it.
+            if driven.job_receiver.is_empty()
+                || driven
+                    .scheduled
+                    .swap(true, std::sync::atomic::Ordering::AcqRel)
+            {
+                return;
+            }
+        }
+    }
+}
 
-                let removables = expired_queue.len();
-                for k in &expired_queue {
-                    map.remove(k);
+impl<K: SandClockInsertion, V> Drop for DrivenTask<K, V> {
+    /// A task dropped by the executor without being run: like a job of the dispatch thread,
+    /// its pending events count as delivered.
+    fn drop(&mut self) {
+        let Some(driven) = self.0.take() else {
+            return;
+        };
+        while let Ok(clock_event) = driven.job_receiver.try_recv() {
+            if let ClockEvent::SandClockDrop = clock_event {
+                let _ = driven.closed.0.try_send(());
+            }
+        }
+        driven
+            .scheduled
+            .store(false, std::sync::atomic::Ordering::Release);
+    }
+}
+
+/// Owner side of the threads started by [`TimerLoop::run()`].
+pub struct LoopHandle<K: SandClockInsertion, V: Send + Sync + 'static = ()> {
+    timer_loop: Arc<Mutex<TimerLoop<K, V>>>,
+    events: EventSender<K, V>,
+    closing_trigger: Arc<AtomicBool>,
+    cancel_pending: Arc<AtomicBool>,
+    wake_sender: Sender<()>,
+    adaptive: bool,
+    driven: Arc<OnceLock<Arc<DrivenDispatch<K, V>>>>,
+    polling_thread: Option<JoinHandle<()>>,
+    dispatch_thread: Option<JoinHandle<ShutdownReport>>,
+}
+
+impl<K: SandClockInsertion, V: Send + Sync + 'static> LoopHandle<K, V> {
+    /// Returns the sender that wakes up the polling thread of an adaptive loop, to be
+    /// used when a key gets a deadline earlier than the one the thread is parked for.
+    ///
+    /// Returns `None` if the loop polls at a fixed frequency, or has no polling thread.
+    #[must_use]
+    pub fn waker(&self) -> Option<Sender<()>> {
+        self.adaptive.then(|| self.wake_sender.clone())
+    }
+    /// Returns the sender of the events of the loop.
+    #[must_use]
+    pub fn event_sender(&self) -> EventSender<K, V> {
+        self.events.clone()
+    }
+    /// Sets the hook run by the loop before the `TimeOut` of each expired key.
+    #[cfg(test)]
+    pub(crate) fn set_expiry_hook(&self, expiry_hook: ExpiryHook) {
+        if let Ok(mut timer_loop) = self.timer_loop.lock() {
+            timer_loop.expiry_hook = Some(expiry_hook);
+        }
+    }
+    /// Asks the loop to stop on its next tick, without waiting for it.
+    pub fn close(&self) {
+        self.closing_trigger
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+        let _ = self.wake_sender.try_send(());
+    }
+    /// Stops the loop and waits for the polling and dispatch threads to end.
+    ///
+    /// Jobs still pending in the dispatch channel are delivered or discarded according to
+    /// `shutdown_policy`.
+    pub fn shutdown(&mut self, shutdown_policy: ShutdownPolicy) -> ShutdownReport {
+        if shutdown_policy == ShutdownPolicy::Cancel {
+            self.cancel_pending
+                .store(true, std::sync::atomic::Ordering::Relaxed);
+        }
+        self.close();
+        if let Some(polling_thread) = self.polling_thread.take() {
+            let _ = polling_thread.join();
+        } else {
+            // no polling thread: the loop is closed here, not on the next tick of the clock.
+            if let Ok(mut timer_loop) = self.timer_loop.lock() {
+                timer_loop.close();
+            }
+        }
+        if let Some(driven) = self.driven.get() {
+            driven.drain();
+            // the final event is in the channel once the loop is closed.
+            if self
+                .timer_loop
+                .lock()
+                .is_ok_and(|timer_loop| timer_loop.closed)
+            {
+                driven.wait_closed();
+            }
+        }
+        match self.dispatch_thread.take() {
+            Some(dispatch_thread) => dispatch_thread.join().unwrap_or_default(),
+            // channel and driven modes: every event sent is in the hands of the user, but
+            // those discarded by the driven dispatch.
+            None => {
+                let dropped = self.driven.get().map_or(0, |driven| driven.dropped());
+                ShutdownReport {
+                    delivered: self.events.sent().saturating_sub(dropped),
+                    dropped,
                 }
-                counter.fetch_sub(removables, std::sync::atomic::Ordering::Relaxed);
             }
-        });
+        }
     }
 }
@@ -0,0 +1,56 @@
+//! `Tracing spans of a clock`, emitted with the `tracing` feature.
+//!
+//! Each span carries a `clock` field, the name set with [`SandClockConfig::name()`]:
+//!
+//! - `sand_clock.tick`, at the debug level, for each polling cycle, with the number of due
+//!   keys `scanned`, the number of keys `expired`, and its `duration_us`;
+//! - `sand_clock.callback`, at the info level, around each run of the callback, with the
+//!   kind of `event` it handles.
+//!
+//! The callback span is entered on the thread that runs the callback, so the spans and
+//! events the callback emits are nested under it.
+//!
+//! [`SandClockConfig::name()`]: crate::SandClockConfig::name
+use std::sync::Arc;
+
+use tracing::{Span, field};
+
+use crate::{SandClockInsertion, user_table::TimeOutCallBack};
+
+/// Creates the span of a polling cycle of the clock `name`, whose fields are recorded by
+/// [`record_tick()`].
+pub(crate) fn tick_span(name: &str) -> Span {
+    tracing::debug_span!(
+        "sand_clock.tick",
+        clock = name,
+        scanned = field::Empty,
+        expired = field::Empty,
+        duration_us = field::Empty,
+    )
+}
+
+/// Records the outcome of a polling cycle in its span.
+pub(crate) fn record_tick(span: &Span, scanned: usize, expired: usize, duration_us: u128) {
+    span.record("scanned", scanned);
+    span.record("expired", expired);
+    span.record(
+        "duration_us",
+        u64::try_from(duration_us).unwrap_or(u64::MAX),
+    );
+}
+
+/// Wraps the callback of the clock `name` to run each event in a `sand_clock.callback` span.
+pub(crate) fn traced_call_back<K: SandClockInsertion, V: 'static>(
+    call_back: TimeOutCallBack<K, V>,
+    name: &str,
+) -> TimeOutCallBack<K, V> {
+    let name: Arc<str> = name.into();
+    Arc::new(move |clock_event| {
+        let span = tracing::info_span!(
+            "sand_clock.callback",
+            clock = &*name,
+            event = clock_event.kind(),
+        );
+        span.in_scope(|| (*call_back)(clock_event));
+    })
+}
@@ -1,101 +1,493 @@
-pub use main_type::SandClock;
+pub use main_type::{SandClock, SandClockBuilder, WeakSandClock};
 pub use sync_insertion::*;
-pub use time_update::{ClockEvent, ClockEventIntern};
-pub use timer_status::TimerStatus;
+pub use time_out::Timer;
+#[cfg(feature = "stream")]
+pub use time_update::EventStream;
+pub use time_update::{ClockEvent, EventReceiver, TimeOutCallBack, TimeOutInfo};
+pub use timer_status::{EntryInfo, ExpiryPolicy, Slot, TimerStatus};
 mod main_type {
     use std::{
         fmt::Debug,
         marker::PhantomData,
-        sync::{
-            Arc,
-            atomic::{AtomicBool, AtomicUsize},
-        },
-        time::Duration,
+        sync::{Arc, Mutex, Weak},
+        time::{Duration, Instant},
     };
 
-    use dashmap::DashMap;
+    use dashmap::{
+        DashMap,
+        mapref::{
+            entry::VacantEntry,
+            one::{MappedRef, MappedRefMut},
+        },
+    };
+    use log::info;
 
     use crate::{
-        ClockEvent, InsertSync, SandClockInsertion, config::SandClockConfig,
-        errors::SandClockError, timer_loop::TimerLoop,
+        ClockEvent, InsertSync, SandClockInsertion,
+        clock::{Clock, SystemClock},
+        config::SandClockConfig,
+        detector::PhiAccrual,
+        errors::SandClockError,
+        executor::{CallBackErrorHook, CallBackPanic, guard_call_back},
+        scheduler::DeadlineQueue,
+        snapshot::{Snapshot, SnapshotEntry},
+        stats::{Counters, SandClockStats},
+        timer_loop::{EventDelivery, EventSender, LoopHandle, ShutdownReport, TimerLoop},
+        user_table::{EventReceiver, TimeOutCallBack},
     };
 
-    use super::timer_status::TimerStatus;
+    #[cfg(feature = "stream")]
+    use crate::user_table::EventStream;
 
-    pub struct SandClockBuilder<K: SandClockInsertion + Debug> {
-        time_out_event_call_back: Option<Arc<dyn Fn(ClockEvent<K>) + Send + Sync + 'static>>,
+    use super::timer_status::{EntryInfo, ExpiryPolicy, Slot, TimerStatus};
+
+    pub struct SandClockBuilder<K: SandClockInsertion + Debug, V: Send + Sync + 'static = ()> {
+        time_out_event_call_back: Option<TimeOutCallBack<K, V>>,
+        on_callback_error: Option<CallBackErrorHook>,
         time_out_duration: Option<Duration>,
+        expiry_policy: ExpiryPolicy,
+        suspect_duration: Option<Duration>,
+        phi_accrual: Option<PhiAccrual>,
+        lifecycle_events: bool,
+        refreshed_every: Option<u64>,
+        #[cfg(feature = "stream")]
+        event_stream_capacity: Option<usize>,
+        #[cfg(feature = "stream")]
+        event_stream: Option<EventStream<K, V>>,
+        clock: Arc<dyn Clock>,
+        restored: Vec<SnapshotEntry<K, V>>,
         config: SandClockConfig,
-        phantom_data: PhantomData<K>,
+        phantom_data: PhantomData<(K, V)>,
     }
-    impl<K: SandClockInsertion + Debug> SandClockBuilder<K> {
+    impl<K: SandClockInsertion + Debug, V: Send + Sync + 'static> SandClockBuilder<K, V> {
         pub fn set_time_out_event(
             &mut self,
-            t_o_event: impl Fn(ClockEvent<K>) + Send + Sync + 'static,
+            t_o_event: impl Fn(ClockEvent<K, V>) + Send + Sync + 'static,
         ) -> &mut Self {
             self.time_out_event_call_back = Some(Arc::new(t_o_event));
+            #[cfg(feature = "stream")]
+            {
+                self.event_stream_capacity = None;
+            }
+            self
+        }
+        /// Reports the panics of the callback set with [`Self::set_time_out_event()`].
+        ///
+        /// A panic of the callback is caught for each event: the dispatcher goes on with the
+        /// next events, the panic is counted in [`SandClock::stats()`], and `on_error` is called
+        /// with its message. Without this hook, panics are only logged. A panic of `on_error`
+        /// itself is caught and logged.
+        ///
+        /// ### Example
+        /// ```rust
+        /// use std::time::Duration;
+        /// use sand_clock::{SandClock, SandClockConfig};
+        ///
+        /// let sessions = SandClock::<u64>::new(SandClockConfig::default())
+        ///     .set_time_out_event(|_clock_event| {
+        ///         // closes the session, and may panic.
+        ///     })
+        ///     .on_callback_error(|call_back_panic| {
+        ///         eprintln!("failed to handle a {} event: {:?}", call_back_panic.event, call_back_panic.message);
+        ///     })
+        ///     .set_time_out_duration(Duration::from_secs(30))
+        ///     .build()
+        ///     .unwrap();
+        /// ```
+        pub fn on_callback_error(
+            &mut self,
+            on_error: impl Fn(CallBackPanic) + Send + Sync + 'static,
+        ) -> &mut Self {
+            self.on_callback_error = Some(Arc::new(on_error));
+            self
+        }
+        /// Delivers the clock events through a stream, taken with [`SandClock::events()`],
+        /// instead of a callback set with [`Self::set_time_out_event()`].
+        ///
+        /// The stream buffers up to `capacity` events. When it is full, the dispatch thread
+        /// waits for the consumer to catch up: events are never dropped, and the following
+        /// ones stay queued inside the clock until there is room again.
+        ///
+        /// `capacity` does not bound the memory of the clock: the timer loop never waits for
+        /// the consumer, and the events a slow consumer has not taken yet keep piling up in
+        /// the unbounded queue of the clock. Watch them with `stats().queued_events`.
+        ///
+        /// Requires the `stream` feature.
+        #[cfg(feature = "stream")]
+        pub fn set_event_stream(&mut self, capacity: usize) -> &mut Self {
+            self.event_stream_capacity = Some(capacity);
+            self.time_out_event_call_back = None;
             self
         }
         pub fn set_time_out_duration(&mut self, time_out_duration: Duration) -> &mut Self {
             self.time_out_duration = Some(time_out_duration);
             self
         }
-        pub fn build(&mut self) -> Result<SandClock<K>, SandClockError> {
-            if let Some(time_out) = self.time_out_event_call_back.take() {
-                let map = Arc::new(DashMap::new());
-
-                let time_out_duration: Duration =
-                    if let Some(duration) = self.time_out_duration.take() {
-                        duration
-                    } else {
-                        return Err(SandClockError::BuildErrorNoDurationSet);
-                    };
-
-                let count = Arc::new(AtomicUsize::new(0));
-                let closing_trigger = Arc::new(AtomicBool::new(false));
-                TimerLoop::run(
-                    &self.config,
-                    &count,
-                    &map,
-                    &time_out,
-                    time_out_duration,
-                    &closing_trigger,
-                );
-                Ok(SandClock {
-                    map,
-                    count,
-                    config: std::mem::take(&mut self.config),
-                    time_out_duration,
-                    closing_trigger,
-                })
+        /// Sets how the deadline of the keys is computed. Defaults to [`ExpiryPolicy::Sliding`].
+        ///
+        /// ### Example
+        /// ```rust
+        /// use std::time::Duration;
+        /// use sand_clock::{ExpiryPolicy, SandClock, SandClockConfig};
+        ///
+        /// // 15 minutes idle, 12 hours at most, whatever the activity.
+        /// let auth_sessions = SandClock::<u64>::new(SandClockConfig::default())
+        ///     .set_time_out_event(|_clock_event| {})
+        ///     .set_time_out_duration(Duration::from_secs(15 * 60))
+        ///     .set_expiry_policy(ExpiryPolicy::SlidingWithCap(Duration::from_secs(12 * 3600)))
+        ///     .build()
+        ///     .unwrap();
+        /// ```
+        pub fn set_expiry_policy(&mut self, expiry_policy: ExpiryPolicy) -> &mut Self {
+            self.expiry_policy = expiry_policy;
+            self
+        }
+        /// Gives the keys an intermediate state before they time out: a key silent for
+        /// `suspect_duration` becomes suspect, and a [`ClockEvent::Suspect`] is sent. If it
+        /// signals again, it gets back to normal with a [`ClockEvent::Recovered`], and if it
+        /// stays silent, it times out as usual.
+        ///
+        /// The suspect duration counts from the last signal of the key, whatever its
+        /// [`ExpiryPolicy`]. It has no effect if it is not shorter than the timeout duration.
+        ///
+        /// ### Example
+        /// ```rust
+        /// use std::time::Duration;
+        /// use sand_clock::{ClockEvent, SandClock, SandClockConfig, TimeOutInfo};
+        ///
+        /// let presence = SandClock::<String>::new(SandClockConfig::default())
+        ///     .set_time_out_event(|clock_event| match clock_event {
+        ///         ClockEvent::Suspect(user) => println!("{user} is away"),
+        ///         ClockEvent::Recovered(user) => println!("{user} is back"),
+        ///         ClockEvent::TimeOut(TimeOutInfo { key, .. }, _) => println!("{key} is offline"),
+        ///         _ => {}
+        ///     })
+        ///     .set_suspect_duration(Duration::from_secs(60))
+        ///     .set_time_out_duration(Duration::from_secs(300))
+        ///     .build()
+        ///     .unwrap();
+        /// ```
+        pub fn set_suspect_duration(&mut self, suspect_duration: Duration) -> &mut Self {
+            self.suspect_duration = Some(suspect_duration);
+            self
+        }
+        /// Times the keys out with a phi-accrual failure detector instead of a fixed timeout.
+        ///
+        /// Each key learns the intervals between its signals, and times out when its
+        /// suspicion level reaches the threshold of `phi_accrual`. The timeout duration stays
+        /// an upper bound of the silence of a key: set it high enough for the detector to
+        /// decide first.
+        ///
+        /// ### Example
+        /// ```rust
+        /// use std::time::Duration;
+        /// use sand_clock::{PhiAccrual, SandClock, SandClockConfig};
+        ///
+        /// let nodes = SandClock::<String>::new(SandClockConfig::default())
+        ///     .set_time_out_event(|_clock_event| {})
+        ///     .set_phi_accrual(
+        ///         PhiAccrual::new(8.0).acceptable_heartbeat_pause(Duration::from_millis(500)),
+        ///     )
+        ///     .set_time_out_duration(Duration::from_secs(60))
+        ///     .build()
+        ///     .unwrap();
+        /// nodes.insert_or_update_timer("10.0.0.1:7000".to_string());
+        /// ```
+        pub fn set_phi_accrual(&mut self, phi_accrual: PhiAccrual) -> &mut Self {
+            self.phi_accrual = Some(phi_accrual);
+            self
+        }
+        /// Sends a [`ClockEvent::Inserted`] when a key is inserted, and a [`ClockEvent::Removed`]
+        /// when it is removed with [`SandClock::remove_key()`], [`SandClock::remove()`] or
+        /// [`SandClock::retain()`], so that the events describe the whole life of the keys.
+        ///
+        /// The events of a key are sent in order: a key inserted again after a timeout or a
+        /// removal gets its `Inserted` after the `TimeOut` or `Removed` of the previous one.
+        ///
+        /// ### Example
+        /// ```rust
+        /// use std::{collections::HashSet, sync::{Arc, Mutex}, time::Duration};
+        /// use sand_clock::{ClockEvent, SandClock, SandClockConfig, TimeOutInfo};
+        ///
+        /// // a replica of the online users, kept in sync by the events.
+        /// let replica = Arc::new(Mutex::new(HashSet::new()));
+        /// let events_replica = replica.clone();
+        /// let presence = SandClock::<u32>::new(SandClockConfig::default())
+        ///     .set_time_out_event(move |clock_event| {
+        ///         let mut replica = events_replica.lock().unwrap();
+        ///         match clock_event {
+        ///             ClockEvent::Inserted(user) => {
+        ///                 replica.insert(user);
+        ///             }
+        ///             ClockEvent::Removed(user) | ClockEvent::TimeOut(TimeOutInfo { key: user, .. }, _) => {
+        ///                 replica.remove(&user);
+        ///             }
+        ///             _ => {}
+        ///         }
+        ///     })
+        ///     .set_lifecycle_events()
+        ///     .set_time_out_duration(Duration::from_secs(30))
+        ///     .build()
+        ///     .unwrap();
+        /// presence.insert_or_update_timer(1);
+        /// ```
+        pub fn set_lifecycle_events(&mut self) -> &mut Self {
+            self.lifecycle_events = true;
+            self
+        }
+        /// Sends a [`ClockEvent::Refreshed`] every `every` signals of a key, its insertion
+        /// excluded: `1` reports every signal, `10` one in ten.
+        pub fn set_refreshed_events(&mut self, every: u64) -> &mut Self {
+            self.refreshed_every = Some(every.max(1));
+            self
+        }
+        /// Sets the source of time of the clock. Defaults to [`SystemClock`].
+        ///
+        /// With a [`ManualClock`](crate::ManualClock), no polling thread is spawned: time only
+        /// moves, and timeouts only fire, when the manual clock is advanced.
+        pub fn set_clock(&mut self, clock: impl Clock) -> &mut Self {
+            self.clock = Arc::new(clock);
+            self
+        }
+        /// Fills the clock with the keys of `snapshot` when it is built.
+        ///
+        /// Each key gets back its value, its timeout duration, and the time to live it had when
+        /// the snapshot was taken: the time spent between the snapshot and the restoration is
+        /// not counted.
+        ///
+        /// ### Example
+        /// ```rust
+        /// use std::time::Duration;
+        /// use sand_clock::{SandClock, SandClockConfig};
+        ///
+        /// let presence = SandClock::<String>::new(SandClockConfig::default())
+        ///     .set_time_out_event(|_clock_event| {})
+        ///     .set_time_out_duration(Duration::from_secs(30))
+        ///     .build()
+        ///     .unwrap();
+        /// presence.insert_or_update_timer("alf".to_string());
+        /// let snapshot = presence.snapshot();
+        /// drop(presence);
+        ///
+        /// // ... after the restart:
+        /// let presence = SandClock::<String>::new(SandClockConfig::default())
+        ///     .set_time_out_event(|_clock_event| {})
+        ///     .set_time_out_duration(Duration::from_secs(30))
+        ///     .restore_from(snapshot)
+        ///     .build()
+        ///     .unwrap();
+        /// assert!(presence.contains_key("alf".to_string()));
+        /// ```
+        pub fn restore_from(&mut self, snapshot: Snapshot<K, V>) -> &mut Self {
+            self.restored = snapshot.entries;
+            self
+        }
+        /// Takes the delivery mode set on the builder, if any.
+        fn take_event_delivery(&mut self) -> Option<EventDelivery<K, V>> {
+            #[cfg(feature = "stream")]
+            if let Some(capacity) = self.event_stream_capacity.take() {
+                let (sender, receiver) = futures::channel::mpsc::channel(capacity);
+                self.event_stream = Some(receiver);
+                return Some(EventDelivery::Stream(sender));
+            }
+            self.time_out_event_call_back
+                .take()
+                .map(EventDelivery::CallBack)
+        }
+        pub fn build(&mut self) -> Result<SandClock<K, V>, SandClockError> {
+            if let Some(event_delivery) = self.take_event_delivery() {
+                self.build_with_delivery(event_delivery)
+                    .map(|(sand_clock, _)| sand_clock)
             } else {
                 Err(SandClockError::BuildErrorNoTimeOutSet)
             }
         }
+        /// Builds the clock without callback: the events are pulled from the returned
+        /// [`EventReceiver`] instead.
+        ///
+        /// The receiver is the unbounded channel the timer loop sends its events to, so no
+        /// dispatch thread is spawned. A callback or stream set on the builder is ignored.
+        ///
+        /// ### Example
+        /// ```rust
+        /// use std::time::Duration;
+        /// use sand_clock::{ClockEvent, SandClock, SandClockConfig, TimeOutInfo};
+        ///
+        /// let (sand_clock, events) = SandClock::<u32>::new(SandClockConfig::default())
+        ///     .set_time_out_duration(Duration::from_millis(100))
+        ///     .build_with_receiver()
+        ///     .unwrap();
+        ///
+        /// sand_clock.insert_or_update_timer(1);
+        /// if let Ok(ClockEvent::TimeOut(TimeOutInfo { key, .. }, _)) = events.recv() {
+        ///     println!("{key} has timed out");
+        /// }
+        /// ```
+        ///
+        /// # Errors
+        /// Returns [`SandClockError::BuildErrorNoDurationSet`] if no timeout duration is set.
+        #[allow(clippy::type_complexity)]
+        pub fn build_with_receiver(
+            &mut self,
+        ) -> Result<(SandClock<K, V>, EventReceiver<K, V>), SandClockError> {
+            self.time_out_event_call_back = None;
+            #[cfg(feature = "stream")]
+            {
+                self.event_stream_capacity = None;
+            }
+            let (sand_clock, receiver) = self.build_with_delivery(EventDelivery::Channel)?;
+            receiver
+                .map(|receiver| (sand_clock, receiver))
+                .ok_or(SandClockError::BuildErrorNoTimeOutSet)
+        }
+        /// Inserts the entries given to [`Self::restore_from()`] in a new map.
+        fn restore(
+            &mut self,
+            map: &DashMap<InsertSync<K>, Slot<V>>,
+            queue: &Mutex<DeadlineQueue<InsertSync<K>>>,
+            counters: &Counters,
+        ) {
+            let Ok(mut queue) = queue.lock() else {
+                return;
+            };
+            for entry in self.restored.drain(..) {
+                let key = entry.key.to_insert_sync();
+                let mut conn_status = TimerStatus::restored(
+                    entry.time_out_duration,
+                    entry.elapsed,
+                    entry.age,
+                    &*self.clock,
+                );
+                conn_status.set_expiry_policy(entry.expiry_policy);
+                conn_status.set_suspect_duration(self.suspect_duration);
+                conn_status.set_phi_accrual(self.phi_accrual);
+                if let Some(deadline) = conn_status.schedule() {
+                    queue.push(key.clone(), deadline);
+                }
+                if map.insert(key, (conn_status, Some(entry.value))).is_none() {
+                    counters.inserted();
+                }
+            }
+        }
+        #[allow(clippy::type_complexity)]
+        fn build_with_delivery(
+            &mut self,
+            event_delivery: EventDelivery<K, V>,
+        ) -> Result<(SandClock<K, V>, Option<EventReceiver<K, V>>), SandClockError> {
+            let map = Arc::new(DashMap::new());
+
+            let time_out_duration: Duration = if let Some(duration) = self.time_out_duration.take()
+            {
+                duration
+            } else {
+                return Err(SandClockError::BuildErrorNoDurationSet);
+            };
+
+            let counters = Arc::new(Counters::new(self.config.get_name()));
+            let queue = Arc::new(Mutex::new(DeadlineQueue::new()));
+            self.restore(&map, &queue, &counters);
+            let event_delivery = match event_delivery {
+                EventDelivery::CallBack(call_back) => EventDelivery::CallBack(guard_call_back(
+                    call_back,
+                    self.on_callback_error.clone(),
+                    counters.clone(),
+                )),
+                event_delivery => event_delivery,
+            };
+            let (loop_handle, receiver) = TimerLoop::run(
+                &self.config,
+                &counters,
+                &map,
+                &queue,
+                &self.clock,
+                event_delivery,
+            );
+            Ok((
+                SandClock {
+                    inner: Arc::new(SandClockInner {
+                        map,
+                        queue,
+                        counters,
+                        config: std::mem::take(&mut self.config),
+                        time_out_duration,
+                        expiry_policy: self.expiry_policy,
+                        clock: self.clock.clone(),
+                        suspect_duration: self.suspect_duration,
+                        phi_accrual: self.phi_accrual,
+                        lifecycle_events: self.lifecycle_events,
+                        refreshed_every: self.refreshed_every,
+                        events: loop_handle.event_sender(),
+                        waker: loop_handle.waker(),
+                        loop_handle: Mutex::new(loop_handle),
+                        #[cfg(feature = "stream")]
+                        event_stream: Mutex::new(self.event_stream.take()),
+                    }),
+                },
+                receiver,
+            ))
+        }
+    }
+
+    /// Handle to a clock and its timer loop.
+    ///
+    /// Clones share the same keys and the same loop, which stops when the last clone is
+    /// dropped. A [`WeakSandClock`] refers to the clock without keeping it alive.
+    pub struct SandClock<K: SandClockInsertion, V: Send + Sync + 'static = ()> {
+        inner: Arc<SandClockInner<K, V>>,
     }
 
-    pub struct SandClock<K: SandClockInsertion> {
-        map: Arc<DashMap<InsertSync<K>, TimerStatus>>,
-        count: Arc<AtomicUsize>,
+    struct SandClockInner<K: SandClockInsertion, V: Send + Sync + 'static> {
+        map: Arc<DashMap<InsertSync<K>, Slot<V>>>,
+        queue: Arc<Mutex<DeadlineQueue<InsertSync<K>>>>,
+        counters: Arc<Counters>,
         config: SandClockConfig,
         time_out_duration: Duration,
-        closing_trigger: Arc<AtomicBool>,
+        expiry_policy: ExpiryPolicy,
+        clock: Arc<dyn Clock>,
+        suspect_duration: Option<Duration>,
+        phi_accrual: Option<PhiAccrual>,
+        lifecycle_events: bool,
+        refreshed_every: Option<u64>,
+        events: EventSender<K, V>,
+        loop_handle: Mutex<LoopHandle<K, V>>,
+        waker: Option<crossbeam_channel::Sender<()>>,
+        #[cfg(feature = "stream")]
+        event_stream: Mutex<Option<EventStream<K, V>>>,
     }
 
-    impl<K: SandClockInsertion> Drop for SandClock<K> {
+    impl<K: SandClockInsertion, V: Send + Sync + 'static> Drop for SandClockInner<K, V> {
         fn drop(&mut self) {
-            self.closing_trigger
-                .store(true, std::sync::atomic::Ordering::Relaxed);
+            if let Ok(loop_handle) = self.loop_handle.lock() {
+                loop_handle.close();
+            }
+        }
+    }
+    impl<K: SandClockInsertion, V: Send + Sync + 'static> Clone for SandClock<K, V> {
+        fn clone(&self) -> Self {
+            Self {
+                inner: self.inner.clone(),
+            }
+        }
+    }
+
+    /// A handle to a [`SandClock`] that does not keep its timer loop alive, taken with
+    /// [`SandClock::downgrade()`].
+    pub struct WeakSandClock<K: SandClockInsertion, V: Send + Sync + 'static = ()> {
+        inner: Weak<SandClockInner<K, V>>,
+    }
+
+    impl<K: SandClockInsertion, V: Send + Sync + 'static> WeakSandClock<K, V> {
+        /// Returns a [`SandClock`] handle, or `None` if every `SandClock` handle was dropped
+        /// and the loop is stopped.
+        #[must_use]
+        pub fn upgrade(&self) -> Option<SandClock<K, V>> {
+            self.inner.upgrade().map(|inner| SandClock { inner })
         }
     }
-    impl<K: SandClockInsertion> Clone for SandClock<K> {
+    impl<K: SandClockInsertion, V: Send + Sync + 'static> Clone for WeakSandClock<K, V> {
         fn clone(&self) -> Self {
             Self {
-                map: self.map.clone(),
-                count: self.count.clone(),
-                config: self.config.clone(),
-                time_out_duration: self.time_out_duration,
-                closing_trigger: self.closing_trigger.clone(),
+                inner: self.inner.clone(),
             }
         }
     }
@@ -110,14 +502,16 @@ mod main_type {
         ///
         /// Internally, the key is wrapped in a type that provides compatibility with [`DashMap`] and atomic operations.
         ///
+        /// To store a value next to each key, use [`SandClock::with_values()`] instead.
+        ///
         /// ### Example
         /// ```rust
         /// use std::time::Duration;
-        /// use sand_clock::{SandClockConfig, SandClock, ClockEvent};
+        /// use sand_clock::{SandClockConfig, SandClock, ClockEvent, TimeOutInfo};
         /// let sand_clock = SandClock::<usize>::new(SandClockConfig::default())
         ///     .set_time_out_event(|clock_event| {
         ///           match clock_event {
-        ///              ClockEvent::TimeOut(key) => {
+        ///              ClockEvent::TimeOut(TimeOutInfo { key, .. }, _) => {
         ///
         ///         println!("Timeout for key: {:?}", key);
         ///              }
@@ -129,16 +523,556 @@ mod main_type {
         #[allow(clippy::new_ret_no_self)]
         #[must_use]
         pub fn new(config: SandClockConfig) -> SandClockBuilder<K> {
+            SandClock::with_values(config)
+        }
+    }
+
+    impl<K: SandClockInsertion + Debug, V: Send + Sync + 'static> SandClock<K, V> {
+        /// Creates a new [`SandClockBuilder<K, V>`] to build a [`SandClock<K, V>`] that stores
+        /// a value of type `V` next to the timer of each key.
+        ///
+        /// Values are inserted with [`Self::insert()`] and handed back, together with their key,
+        /// in [`ClockEvent::TimeOut`] when the key expires.
+        ///
+        /// ### Example
+        /// ```rust
+        /// use std::time::Duration;
+        /// use sand_clock::{SandClockConfig, SandClock, ClockEvent, TimeOutInfo};
+        ///
+        /// struct Session {
+        ///     user_name: String,
+        /// }
+        ///
+        /// let sessions = SandClock::<u64, Session>::with_values(SandClockConfig::default())
+        ///     .set_time_out_event(|clock_event| {
+        ///         if let ClockEvent::TimeOut(TimeOutInfo { key: id, .. }, session) = clock_event {
+        ///             println!("Session {id} of {} has expired", session.user_name);
+        ///         }
+        ///     })
+        ///     .set_time_out_duration(Duration::from_secs(1))
+        ///     .build()
+        ///     .unwrap();
+        ///
+        /// sessions.insert(7, Session { user_name: "alf".to_string() });
+        /// assert_eq!(sessions.get(&7).unwrap().user_name, "alf");
+        /// ```
+        #[allow(clippy::new_ret_no_self)]
+        #[must_use]
+        pub fn with_values(config: SandClockConfig) -> SandClockBuilder<K, V> {
             SandClockBuilder {
                 time_out_event_call_back: None,
+                on_callback_error: None,
                 time_out_duration: None,
+                expiry_policy: ExpiryPolicy::default(),
+                suspect_duration: None,
+                phi_accrual: None,
+                lifecycle_events: false,
+                refreshed_every: None,
+                #[cfg(feature = "stream")]
+                event_stream_capacity: None,
+                #[cfg(feature = "stream")]
+                event_stream: None,
+                clock: Arc::new(SystemClock),
+                restored: vec![],
                 config,
-                phantom_data: PhantomData::<K>,
+                phantom_data: PhantomData::<(K, V)>,
+            }
+        }
+        /// Inserts `value` for `key` and refreshes the key's timer.
+        ///
+        /// Returns the previous value if the key was already tracked. A new key gets the
+        /// default timeout duration, an existing one keeps its own.
+        pub fn insert(&self, key: K, value: V) -> Option<V> {
+            self.insert_with(key, value, |_| {})
+        }
+        /// Inserts `value` for `key` and refreshes the key's timer, like [`Self::insert`], but
+        /// with its own timeout duration, like [`Self::insert_or_update_timer_with_timeout`].
+        ///
+        /// ### Example
+        /// ```rust
+        /// use std::time::Duration;
+        /// use sand_clock::{SandClock, SandClockConfig};
+        ///
+        /// struct Session {
+        ///     user: String,
+        /// }
+        ///
+        /// let sessions = SandClock::<u64, Session>::with_values(SandClockConfig::default())
+        ///     .set_time_out_event(|_clock_event| {})
+        ///     .set_time_out_duration(Duration::from_secs(30))
+        ///     .build()
+        ///     .unwrap();
+        ///
+        /// let admin = Session { user: "admin".to_string() };
+        /// sessions.insert_with_timeout(1, admin, Duration::from_secs(3600));
+        /// ```
+        pub fn insert_with_timeout(
+            &self,
+            key: K,
+            value: V,
+            time_out_duration: Duration,
+        ) -> Option<V> {
+            self.insert_with(key, value, |conn_status| {
+                conn_status.set_time_out_duration(time_out_duration);
+            })
+        }
+        /// Inserts `value` for `key` and refreshes the key's timer, like [`Self::insert`], but
+        /// with its own expiry policy, like [`Self::insert_or_update_timer_with_policy`].
+        pub fn insert_with_policy(
+            &self,
+            key: K,
+            value: V,
+            expiry_policy: ExpiryPolicy,
+        ) -> Option<V> {
+            self.insert_with(key, value, |conn_status| {
+                conn_status.set_expiry_policy(expiry_policy);
+            })
+        }
+        /// Inserts `value` for `key`, after `configure` has changed its timer.
+        fn insert_with(&self, key: K, value: V, configure: impl Fn(&mut TimerStatus)) -> Option<V> {
+            let (old_value, scheduled) = match self.inner.map.entry(key.to_insert_sync()) {
+                dashmap::Entry::Occupied(entry) => {
+                    let mut entry = entry.into_ref();
+                    let (key, (conn_status, old_value)) = entry.pair_mut();
+                    configure(conn_status);
+                    let scheduled = self.signal(key, conn_status);
+                    (old_value.replace(value), scheduled)
+                }
+                dashmap::Entry::Vacant(entry) => {
+                    let mut conn_status = self.new_timer_status();
+                    configure(&mut conn_status);
+                    (None, self.insert_vacant(entry, conn_status, value))
+                }
+            };
+            self.schedule(scheduled);
+            old_value
+        }
+        /// Refreshes the timer of `key` only if it is tracked, and returns whether it was.
+        ///
+        /// Unlike [`Self::insert_or_update_timer`], a late signal of a key that already timed
+        /// out does not bring it back: the caller can insert it again explicitly, e.g. with
+        /// [`Self::insert_if_absent`].
+        pub fn touch(&self, key: &K) -> bool {
+            let Some(mut entry) = self.inner.map.get_mut(&key.clone().to_insert_sync()) else {
+                return false;
+            };
+            let (key, (conn_status, _)) = entry.pair_mut();
+            let scheduled = self.signal(key, conn_status);
+            drop(entry);
+            self.schedule(scheduled);
+            true
+        }
+        /// Returns a reference to the value stored for `key`, if it is tracked.
+        ///
+        /// Reading a value does not count as activity: the timer is left untouched.
+        /// The returned guard locks the map shard of the key, so keep it short-lived.
+        pub fn get(&self, key: &K) -> Option<MappedRef<'_, InsertSync<K>, Slot<V>, V>> {
+            self.inner
+                .map
+                .get(&key.clone().to_insert_sync())
+                .and_then(|entry| entry.try_map(|(_, value)| value.as_ref()).ok())
+        }
+        /// Returns a mutable reference to the value stored for `key`, if it is tracked.
+        ///
+        /// Like [`Self::get()`], this does not refresh the timer.
+        pub fn get_mut(&self, key: &K) -> Option<MappedRefMut<'_, InsertSync<K>, Slot<V>, V>> {
+            self.inner
+                .map
+                .get_mut(&key.clone().to_insert_sync())
+                .and_then(|entry| entry.try_map(|(_, value)| value.as_mut()).ok())
+        }
+        /// Removes `key` from the clock and returns its value. No timeout event is raised.
+        pub fn remove(&self, key: &K) -> Option<V> {
+            self.remove_entry(key.clone().to_insert_sync())
+        }
+        /// Takes the stream of clock events enabled with [`SandClockBuilder::set_event_stream()`].
+        ///
+        /// The stream can be taken once, by any clone of the clock. It ends after
+        /// [`ClockEvent::SandClockDrop`], once the clock is dropped.
+        ///
+        /// Requires the `stream` feature.
+        ///
+        /// ### Example
+        /// ```rust
+        /// use futures::StreamExt;
+        /// use std::time::Duration;
+        /// use sand_clock::{ClockEvent, SandClock, SandClockConfig, TimeOutInfo};
+        ///
+        /// let sand_clock = SandClock::<u32>::new(SandClockConfig::default())
+        ///     .set_event_stream(64)
+        ///     .set_time_out_duration(Duration::from_millis(100))
+        ///     .build()
+        ///     .unwrap();
+        /// let mut events = sand_clock.events().unwrap();
+        ///
+        /// sand_clock.insert_or_update_timer(1);
+        /// futures::executor::block_on(async {
+        ///     if let Some(ClockEvent::TimeOut(TimeOutInfo { key, .. }, _)) = events.next().await {
+        ///         println!("{key} has timed out");
+        ///     }
+        /// });
+        /// ```
+        ///
+        /// # Errors
+        /// Returns [`SandClockError::NoEventStream`] if the builder had no event stream set,
+        /// or if the stream was already taken.
+        #[cfg(feature = "stream")]
+        pub fn events(&self) -> Result<EventStream<K, V>, SandClockError> {
+            self.inner
+                .event_stream
+                .lock()
+                .ok()
+                .and_then(|mut event_stream| event_stream.take())
+                .ok_or(SandClockError::NoEventStream)
+        }
+        /// Creates the timer of a new key, with the timeout duration, expiry policy, suspect
+        /// duration and failure detector of the clock.
+        fn new_timer_status(&self) -> TimerStatus {
+            let mut conn_status =
+                TimerStatus::new(self.inner.time_out_duration, &*self.inner.clock);
+            conn_status.set_expiry_policy(self.inner.expiry_policy);
+            conn_status.set_suspect_duration(self.inner.suspect_duration);
+            conn_status.set_phi_accrual(self.inner.phi_accrual);
+            conn_status
+        }
+        /// Records a signal of a tracked key, while its map entry is locked.
+        ///
+        /// A suspect key recovers. Returns the key and its next check if it has to be
+        /// scheduled again, e.g. after a shorter timeout duration or a stricter policy.
+        fn signal(
+            &self,
+            key: &InsertSync<K>,
+            conn_status: &mut TimerStatus,
+        ) -> Option<(InsertSync<K>, Instant)> {
+            conn_status.heartbeat(&*self.inner.clock);
+            self.inner.counters.refreshed();
+            if let Some(every) = self.inner.refreshed_every
+                && (conn_status.time_out_info().heartbeats() - 1).is_multiple_of(every)
+            {
+                self.send_event(ClockEvent::Refreshed(key.clone().into_inner()));
+            }
+            // sent under the lock of the key, so it cannot overtake the `Suspect` event.
+            if conn_status.recover() {
+                self.send_event(ClockEvent::Recovered(key.clone().into_inner()));
+            }
+            if conn_status.needs_earlier_schedule() {
+                conn_status
+                    .schedule()
+                    .map(|deadline| (key.clone(), deadline))
+            } else {
+                None
+            }
+        }
+        /// Pushes a key in the deadline queue of the timer loop.
+        ///
+        /// Called once the map entry is released, so the map shard and the queue are never
+        /// locked together.
+        fn schedule(&self, scheduled: Option<(InsertSync<K>, Instant)>) {
+            if let Some((key, deadline)) = scheduled
+                && let Ok(mut queue) = self.inner.queue.lock()
+            {
+                let earliest = queue
+                    .next_deadline()
+                    .is_none_or(|next_deadline| deadline < next_deadline);
+                queue.push(key, deadline);
+                drop(queue);
+                // an adaptive loop is parked until the previous earliest deadline.
+                if earliest && let Some(waker) = &self.inner.waker {
+                    let _ = waker.try_send(());
+                }
+            }
+        }
+        /// Sends an event from a clock handle, to the same channel as the timer loop.
+        fn send_event(&self, clock_event: ClockEvent<K, V>) {
+            if let Err(e) = self.inner.events.send(clock_event) {
+                info!("failed to externalize the clock event [{e:?}]");
+            }
+        }
+        /// Inserts a new key in its vacant entry, and returns its first check to schedule.
+        ///
+        /// The `Inserted` event is sent before the entry is released, so that it cannot
+        /// overtake the other events of the key.
+        fn insert_vacant(
+            &self,
+            entry: VacantEntry<'_, InsertSync<K>, Slot<V>>,
+            mut conn_status: TimerStatus,
+            value: V,
+        ) -> Option<(InsertSync<K>, Instant)> {
+            self.inner.counters.inserted();
+            let key = entry.key().clone();
+            let deadline = conn_status.schedule();
+            if self.inner.lifecycle_events {
+                self.send_event(ClockEvent::Inserted(key.clone().into_inner()));
+            }
+            entry.insert((conn_status, Some(value)));
+            deadline.map(|deadline| (key, deadline))
+        }
+        /// Removes a key and returns its value, sending `Removed` while its entry is locked.
+        fn remove_entry(&self, key: InsertSync<K>) -> Option<V> {
+            let dashmap::Entry::Occupied(entry) = self.inner.map.entry(key) else {
+                return None;
+            };
+            self.inner.counters.removed();
+            if self.inner.lifecycle_events {
+                self.send_event(ClockEvent::Removed(entry.key().clone().into_inner()));
+            }
+            let (_, (_, value)) = entry.remove_entry();
+            value
+        }
+        pub fn remove_key(&self, key: K) {
+            self.remove_entry(key.to_insert_sync());
+        }
+        pub fn contains_key(&self, key: K) -> bool {
+            self.inner.map.contains_key(&key.to_insert_sync())
+        }
+        /// Returns the time left before `key` times out if it stays silent, or `None` if the
+        /// key is not tracked. A key that is due but not expired yet has no time left.
+        pub fn time_remaining(&self, key: &K) -> Option<Duration> {
+            let now = self.inner.clock.now();
+            self.with_timer_status(key, |timer_status| {
+                timer_status.deadline().map_or(Duration::MAX, |deadline| {
+                    deadline.saturating_duration_since(now)
+                })
+            })
+        }
+        /// Returns the instant of the last signal of `key`, or `None` if the key is not tracked.
+        pub fn last_seen(&self, key: &K) -> Option<Instant> {
+            self.with_timer_status(key, |timer_status| {
+                timer_status.time_out_info().get_last_instant_update()
+            })
+        }
+        /// Returns the time elapsed since the insertion of `key`, or `None` if the key is not
+        /// tracked.
+        pub fn age(&self, key: &K) -> Option<Duration> {
+            let now = self.inner.clock.now();
+            self.with_timer_status(key, |timer_status| {
+                now.saturating_duration_since(timer_status.time_out_info().first_seen())
+            })
+        }
+        /// Returns the suspicion level of `key`, if it is tracked by a clock in phi-accrual
+        /// mode, see [`SandClockBuilder::set_phi_accrual()`].
+        pub fn phi(&self, key: &K) -> Option<f64> {
+            let now = self.inner.clock.now();
+            self.with_timer_status(key, |timer_status| timer_status.phi(now))
+                .flatten()
+        }
+        fn with_timer_status<T>(&self, key: &K, read: impl FnOnce(&TimerStatus) -> T) -> Option<T> {
+            self.inner
+                .map
+                .get(&key.clone().to_insert_sync())
+                .map(|entry| read(&entry.value().0))
+        }
+        /// Returns the tracked keys.
+        ///
+        /// Like [`iter()`](Self::iter), the keys are collected before being returned.
+        pub fn keys(&self) -> impl Iterator<Item = K> + use<K, V> {
+            self.iter().map(|(key, _)| key)
+        }
+        /// Returns the tracked keys with the state of their timer.
+        ///
+        /// The entries are collected before being returned, so that no lock is held on the
+        /// map while iterating: the clock can be used, and the loop keeps expiring keys, in the
+        /// meantime.
+        ///
+        /// ### Example
+        /// ```rust
+        /// use std::time::Duration;
+        /// use sand_clock::{SandClock, SandClockConfig};
+        ///
+        /// let users = SandClock::<String>::new(SandClockConfig::default())
+        ///     .set_time_out_event(|_clock_event| {})
+        ///     .set_time_out_duration(Duration::from_secs(60))
+        ///     .build()
+        ///     .unwrap();
+        /// users.insert_or_update_timer("alf".to_string());
+        /// users.insert_or_update_timer("bob".to_string());
+        ///
+        /// // the most recently active users first.
+        /// let mut online: Vec<_> = users.iter().collect();
+        /// online.sort_by_key(|(_, info)| std::cmp::Reverse(info.last_seen));
+        /// assert_eq!(online.len(), 2);
+        /// ```
+        pub fn iter(&self) -> impl Iterator<Item = (K, EntryInfo)> + use<K, V> {
+            self.inner
+                .map
+                .iter()
+                .map(|entry| {
+                    (
+                        entry.key().clone().into_inner(),
+                        EntryInfo::new(&entry.value().0),
+                    )
+                })
+                .collect::<Vec<_>>()
+                .into_iter()
+        }
+        /// Keeps only the keys for which `keep` returns `true`, and removes the others.
+        ///
+        /// No timeout is raised for the removed keys. Each of them sends `Removed` when
+        /// lifecycle events are enabled, see [`SandClockBuilder::set_lifecycle_events()`].
+        ///
+        /// `keep` runs while the shard of the key is locked: it must not use this clock.
+        pub fn retain(&self, mut keep: impl FnMut(&K, &EntryInfo) -> bool) {
+            self.inner.map.retain(|key, (timer_status, _)| {
+                let kept = keep(key, &EntryInfo::new(timer_status));
+                if !kept {
+                    self.inner.counters.removed();
+                    if self.inner.lifecycle_events {
+                        self.send_event(ClockEvent::Removed(key.clone().into_inner()));
+                    }
+                }
+                kept
+            });
+        }
+        /// Returns the keys that time out within `within` if they stay silent, the soonest
+        /// first and the keys without deadline last.
+        pub fn expiring_within(
+            &self,
+            within: Duration,
+        ) -> impl Iterator<Item = (K, EntryInfo)> + use<K, V> {
+            let now = self.inner.clock.now();
+            let mut expiring: Vec<_> = self
+                .iter()
+                .filter(|(_, info)| info.time_remaining(now) <= within)
+                .collect();
+            // a key without deadline never times out, it comes after all the others.
+            expiring.sort_by_key(|(_, info)| (info.deadline.is_none(), info.deadline));
+            expiring.into_iter()
+        }
+        /// Creates a [`WeakSandClock`] handle, which does not keep the timer loop alive.
+        ///
+        /// ### Example
+        /// ```rust
+        /// use std::time::Duration;
+        /// use sand_clock::{SandClock, SandClockConfig};
+        ///
+        /// let sand_clock = SandClock::<u32>::new(SandClockConfig::default())
+        ///     .set_time_out_event(|_clock_event| {})
+        ///     .set_time_out_duration(Duration::from_secs(1))
+        ///     .build()
+        ///     .unwrap();
+        /// let weak = sand_clock.downgrade();
+        /// assert!(weak.upgrade().is_some());
+        ///
+        /// drop(sand_clock);
+        /// assert!(weak.upgrade().is_none());
+        /// ```
+        #[must_use]
+        pub fn downgrade(&self) -> WeakSandClock<K, V> {
+            WeakSandClock {
+                inner: Arc::downgrade(&self.inner),
             }
         }
+        #[must_use]
+        pub fn get_entries_count(&self) -> usize {
+            self.inner.counters.entries()
+        }
+        /// Returns the counters of the clock: tracked keys, inserts, signals, timeouts and
+        /// removals, events waiting for delivery, and duration of the last polling cycle.
+        ///
+        /// ### Example
+        /// ```rust
+        /// use std::time::Duration;
+        /// use sand_clock::{SandClock, SandClockConfig};
+        ///
+        /// let presence = SandClock::<u32>::new(SandClockConfig::default())
+        ///     .set_time_out_event(|_clock_event| {})
+        ///     .set_time_out_duration(Duration::from_secs(30))
+        ///     .build()
+        ///     .unwrap();
+        /// presence.insert_or_update_timer(1);
+        /// presence.insert_or_update_timer(1);
+        ///
+        /// let stats = presence.stats();
+        /// assert_eq!((stats.entries, stats.inserts, stats.refreshes), (1, 1, 1));
+        /// ```
+        pub fn stats(&self) -> SandClockStats {
+            self.inner.counters.stats(self.inner.events.queued())
+        }
+        /// Stops the timer loop and waits for its background threads to end.
+        ///
+        /// Timeout events still waiting to be delivered are handled according to the
+        /// [`ShutdownPolicy`](crate::config::ShutdownPolicy) of the config: delivered with
+        /// `Drain` (the default), discarded with `Cancel`. [`ClockEvent::SandClockDrop`] is
+        /// delivered last in both cases, and no event follows once `shutdown` returns.
+        ///
+        /// The loop is shared by all the clones of the clock, which stop with it.
+        ///
+        /// Must not be called from the timeout callback, which would wait for itself. With a
+        /// stream, `shutdown` waits for the consumer to make room for the pending events.
+        ///
+        /// ### Example
+        /// ```rust
+        /// use std::time::Duration;
+        /// use sand_clock::{SandClock, SandClockConfig};
+        ///
+        /// let sand_clock = SandClock::<u32>::new(SandClockConfig::default())
+        ///     .set_time_out_event(|_clock_event| {})
+        ///     .set_time_out_duration(Duration::from_secs(1))
+        ///     .build()
+        ///     .unwrap();
+        /// sand_clock.insert_or_update_timer(1);
+        ///
+        /// let report = sand_clock.shutdown();
+        /// assert_eq!(report.dropped, 0);
+        /// ```
+        pub fn shutdown(self) -> ShutdownReport {
+            let shutdown_policy = self.inner.config.get_shutdown_policy();
+            self.inner
+                .loop_handle
+                .lock()
+                .map(|mut loop_handle| loop_handle.shutdown(shutdown_policy))
+                .unwrap_or_default()
+        }
+        /// Runs `expiry_hook` in the timer loop before the `TimeOut` of each expired key,
+        /// while its entry is locked.
+        #[cfg(test)]
+        pub(crate) fn set_expiry_hook(&self, expiry_hook: impl Fn() + Send + Sync + 'static) {
+            if let Ok(loop_handle) = self.inner.loop_handle.lock() {
+                loop_handle.set_expiry_hook(Arc::new(expiry_hook));
+            }
+        }
+    }
+
+    impl<K: SandClockInsertion + Debug, V: Clone + Send + Sync + 'static> SandClock<K, V> {
+        /// Takes a [`Snapshot`] of the tracked keys, to restore them later with
+        /// [`SandClockBuilder::restore_from()`], e.g. across a process restart.
+        ///
+        /// Each key is saved with its value, its timeout duration, and the time elapsed since
+        /// its last signal. Keys that are signaled while the snapshot is taken may be saved
+        /// before or after their update.
+        #[must_use]
+        pub fn snapshot(&self) -> Snapshot<K, V> {
+            let now = self.inner.clock.now();
+            Snapshot {
+                entries: self
+                    .inner
+                    .map
+                    .iter()
+                    .filter_map(|entry| {
+                        let (conn_status, value) = entry.value();
+                        Some(SnapshotEntry {
+                            key: entry.key().clone().into_inner(),
+                            value: value.clone()?,
+                            elapsed: now.saturating_duration_since(
+                                conn_status.time_out_info().get_last_instant_update(),
+                            ),
+                            age: now.saturating_duration_since(
+                                conn_status.time_out_info().first_seen(),
+                            ),
+                            time_out_duration: conn_status.time_out_duration(),
+                            expiry_policy: conn_status.expiry_policy(),
+                        })
+                    })
+                    .collect(),
+            }
+        }
+    }
+
+    impl<K: SandClockInsertion + Debug, V: Default + Send + Sync + 'static> SandClock<K, V> {
         ////// Inserts a new key into the `SandClock`, or updates its timer if it already exists.
         ///
-        /// When inserting a key for the first time, a new [`TimerStatus`] is created and tracked.
+        /// When inserting a key for the first time, a new [`TimerStatus`] is created and tracked,
+        /// together with `V::default()` as value.
         /// If the key already exists, its associated timer is refreshed with a new [`Instant::now()`],
         /// effectively extending its lifetime within the clock.
         ///
@@ -153,7 +1087,7 @@ mod main_type {
         ///     .set_time_out_event(|clock_event| {
         ///     
         ///           match clock_event {
-        ///              ClockEvent::TimeOut(key) => {
+        ///              ClockEvent::TimeOut(TimeOutInfo { key, .. }, _) => {
         ///
         ///         println!("Timeout for key: {:?}", key);
         ///              }
@@ -164,35 +1098,138 @@ mod main_type {
         ///  use sand_clock::prelude::*;
         /// sand_clock.insert_or_update_timer(0);
         /// ```
+        ///
+        /// [`Instant::now()`]: std::time::Instant::now
         pub fn insert_or_update_timer(&self, key: K) {
-            self.map
-                .entry(key.to_insert_sync())
-                .and_modify(|conn_status| conn_status.time_out_handler().update_timer())
-                .or_insert_with(|| {
-                    self.count
-                        .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-                    TimerStatus::default()
-                });
+            let scheduled = match self.inner.map.entry(key.to_insert_sync()) {
+                dashmap::Entry::Occupied(entry) => {
+                    let mut entry = entry.into_ref();
+                    let (key, (conn_status, _)) = entry.pair_mut();
+                    self.signal(key, conn_status)
+                }
+                dashmap::Entry::Vacant(entry) => {
+                    self.insert_vacant(entry, self.new_timer_status(), V::default())
+                }
+            };
+            self.schedule(scheduled);
         }
-        pub fn remove_key(&self, key: K) {
-            if self.map.remove(&key.to_insert_sync()).is_some() {
-                self.count
-                    .fetch_sub(1, std::sync::atomic::Ordering::Relaxed);
-            }
+        /// Inserts `key` only if it is not tracked, and returns whether it was inserted.
+        ///
+        /// The timer of a tracked key is left untouched.
+        ///
+        /// ### Example
+        /// ```rust
+        /// use std::time::Duration;
+        /// use sand_clock::{SandClock, SandClockConfig};
+        /// let devices = SandClock::<u32>::new(SandClockConfig::default())
+        ///     .set_time_out_event(|_clock_event| {})
+        ///     .set_time_out_duration(Duration::from_secs(30))
+        ///     .build()
+        ///     .unwrap();
+        ///
+        /// let ping = |device| {
+        ///     if !devices.touch(&device) && devices.insert_if_absent(device) {
+        ///         println!("{device} is connected");
+        ///     }
+        /// };
+        /// ping(7);
+        /// ping(7);
+        /// ```
+        pub fn insert_if_absent(&self, key: K) -> bool {
+            let scheduled = match self.inner.map.entry(key.to_insert_sync()) {
+                dashmap::Entry::Occupied(_) => return false,
+                dashmap::Entry::Vacant(entry) => {
+                    self.insert_vacant(entry, self.new_timer_status(), V::default())
+                }
+            };
+            self.schedule(scheduled);
+            true
         }
-        pub fn contains_key(&self, key: K) -> bool {
-            self.map.contains_key(&key.to_insert_sync())
+        /// Inserts or refreshes a key, like [`Self::insert_or_update_timer`], but with its own
+        /// timeout duration instead of the one given to [`SandClockBuilder::set_time_out_duration`].
+        ///
+        /// The duration is stored in the key's [`TimerStatus`] and replaces any previous one,
+        /// so a key can move from one tier to another on its next signal. Keys inserted with
+        /// [`Self::insert_or_update_timer`] keep the duration they already have.
+        ///
+        /// ### Example
+        /// ```rust
+        /// use std::time::Duration;
+        /// use sand_clock::{SandClockConfig, SandClock};
+        /// let sand_clock = SandClock::<&'static str>::new(SandClockConfig::default())
+        ///     .set_time_out_event(|_clock_event| {})
+        ///     .set_time_out_duration(Duration::from_secs(30))
+        ///     .build()
+        ///     .unwrap();
+        ///
+        /// sand_clock.insert_or_update_timer("anonymous");
+        /// sand_clock.insert_or_update_timer_with_timeout("service", Duration::from_secs(3600));
+        /// ```
+        pub fn insert_or_update_timer_with_timeout(&self, key: K, time_out_duration: Duration) {
+            self.insert_or_update_timer_with(key, |conn_status| {
+                conn_status.set_time_out_duration(time_out_duration);
+            });
         }
-        #[must_use]
-        pub fn get_entries_count(&self) -> usize {
-            self.count.load(std::sync::atomic::Ordering::Relaxed)
+        /// Inserts or refreshes a key, like [`Self::insert_or_update_timer`], but with its own
+        /// expiry policy instead of the one given to [`SandClockBuilder::set_expiry_policy`].
+        ///
+        /// The policy replaces any previous one of the key. Under [`ExpiryPolicy::Absolute`],
+        /// the signals of the key do not push its deadline back.
+        ///
+        /// ### Example
+        /// ```rust
+        /// use std::time::Duration;
+        /// use sand_clock::{ExpiryPolicy, SandClock, SandClockConfig};
+        /// let sessions = SandClock::<&'static str>::new(SandClockConfig::default())
+        ///     .set_time_out_event(|_clock_event| {})
+        ///     .set_time_out_duration(Duration::from_secs(30 * 60))
+        ///     .build()
+        ///     .unwrap();
+        ///
+        /// // a guest session lasts 30 minutes, active or not.
+        /// sessions.insert_or_update_timer_with_policy("guest", ExpiryPolicy::Absolute);
+        /// ```
+        pub fn insert_or_update_timer_with_policy(&self, key: K, expiry_policy: ExpiryPolicy) {
+            self.insert_or_update_timer_with(key, |conn_status| {
+                conn_status.set_expiry_policy(expiry_policy);
+            });
+        }
+        /// Inserts or refreshes a key, after `configure` has changed its timer.
+        fn insert_or_update_timer_with(&self, key: K, configure: impl Fn(&mut TimerStatus)) {
+            let scheduled = match self.inner.map.entry(key.to_insert_sync()) {
+                dashmap::Entry::Occupied(entry) => {
+                    let mut entry = entry.into_ref();
+                    let (key, (conn_status, _)) = entry.pair_mut();
+                    configure(conn_status);
+                    self.signal(key, conn_status)
+                }
+                dashmap::Entry::Vacant(entry) => {
+                    let mut conn_status = self.new_timer_status();
+                    configure(&mut conn_status);
+                    self.insert_vacant(entry, conn_status, V::default())
+                }
+            };
+            self.schedule(scheduled);
         }
     }
 }
 
 mod timer_status {
+    use std::time::{Duration, Instant};
+
+    use crate::{
+        clock::Clock,
+        detector::{HeartbeatHistory, PhiAccrual},
+    };
+
     use super::time_out::Timer;
 
+    /// The map entry of a tracked key: its [`TimerStatus`] and its value.
+    ///
+    /// The value is always present while the key is in the map: the timer loop takes it out
+    /// only to send the `TimeOut` of the key, right before removing the entry.
+    pub type Slot<V> = (TimerStatus, Option<V>);
+
     /// Stores timeout-related state for a key registered in the [`SandClock`].
     ///
     /// This struct keeps track of whether the associated entity has expired,
@@ -204,39 +1241,246 @@ mod timer_status {
     /// ### Fields
     /// - `expired`: A flag indicating whether the timeout has already occurred.
     /// - `time_out`: A [`Timer`] that tracks the time since last activity.
+    /// - `time_out_duration`: The inactivity duration after which this key times out.
+    /// - `scheduled`: The deadline under which the key currently sits in the loop's deadline queue.
+    /// - `expiry_policy`: Whether the signals of the key push its deadline back.
+    /// - `suspect_duration`: The inactivity duration after which this key becomes suspect.
+    /// - `suspect`: A flag indicating whether the key is currently suspect.
+    /// - `heartbeat_history`: The intervals between the signals of the key, in phi-accrual mode.
 
     #[derive(Clone)]
     pub struct TimerStatus {
         expired: bool,
         time_out: Timer,
+        time_out_duration: Duration,
+        scheduled: Option<Instant>,
+        expiry_policy: ExpiryPolicy,
+        suspect_duration: Option<Duration>,
+        suspect: bool,
+        heartbeat_history: Option<HeartbeatHistory>,
     }
 
-    impl Default for TimerStatus {
-        fn default() -> Self {
+    /// How the deadline of a key is computed from its timer.
+    ///
+    /// Set for a whole clock with `SandClockBuilder::set_expiry_policy()`, or for a key
+    /// with `SandClock::insert_or_update_timer_with_policy()`.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub enum ExpiryPolicy {
+        /// The key times out after the timeout duration without signal: each signal
+        /// pushes the deadline back.
+        #[default]
+        Sliding,
+        /// The key times out the timeout duration after its insertion, whatever its signals.
+        Absolute,
+        /// Sliding, but the key times out at the latest the given duration after its
+        /// insertion: an idle timeout with a hard cap.
+        SlidingWithCap(Duration),
+    }
+
+    impl TimerStatus {
+        /// Creates a new, non-expired [`TimerStatus`] with a fresh internal timer started
+        /// at the current time of `clock`, timing out after `time_out_duration` of inactivity.
+        #[must_use]
+        pub fn new(time_out_duration: Duration, clock: &dyn Clock) -> Self {
             Self {
                 expired: false,
-                time_out: Timer::new(),
+                time_out: Timer::new(clock),
+                time_out_duration,
+                scheduled: None,
+                expiry_policy: ExpiryPolicy::default(),
+                suspect_duration: None,
+                suspect: false,
+                heartbeat_history: None,
             }
         }
-    }
-    impl TimerStatus {
-        /// Creates a new, non-expired [`TimerStatus`] with a fresh internal timer.
+        /// Creates a [`TimerStatus`] whose last signal happened `elapsed` ago, and whose
+        /// insertion happened `age` ago, according to `clock`.
+        ///
+        /// Used to restore a key from a snapshot with the time to live it had left.
         #[must_use]
-        pub fn new() -> Self {
+        pub fn restored(
+            time_out_duration: Duration,
+            elapsed: Duration,
+            age: Duration,
+            clock: &dyn Clock,
+        ) -> Self {
+            let now = clock.now();
+            let last_update = now.checked_sub(elapsed).unwrap_or(now);
             Self {
                 expired: false,
-                time_out: Timer::new(),
+                time_out: Timer::with_updates(
+                    now.checked_sub(age).unwrap_or(last_update).min(last_update),
+                    last_update,
+                ),
+                time_out_duration,
+                scheduled: None,
+                expiry_policy: ExpiryPolicy::default(),
+                suspect_duration: None,
+                suspect: false,
+                heartbeat_history: None,
+            }
+        }
+        /// Returns the inactivity duration after which this key times out.
+        #[must_use]
+        pub fn time_out_duration(&self) -> Duration {
+            self.time_out_duration
+        }
+        /// Replaces the inactivity duration after which this key times out.
+        pub fn set_time_out_duration(&mut self, time_out_duration: Duration) {
+            self.time_out_duration = time_out_duration;
+        }
+        /// Returns how the deadline of this key is computed.
+        #[must_use]
+        pub fn expiry_policy(&self) -> ExpiryPolicy {
+            self.expiry_policy
+        }
+        /// Replaces how the deadline of this key is computed.
+        pub fn set_expiry_policy(&mut self, expiry_policy: ExpiryPolicy) {
+            self.expiry_policy = expiry_policy;
+        }
+        /// Switches this key to phi-accrual failure detection, or back to a fixed timeout
+        /// with `None`. The intervals recorded so far are dropped.
+        pub fn set_phi_accrual(&mut self, phi_accrual: Option<PhiAccrual>) {
+            self.heartbeat_history = phi_accrual.map(HeartbeatHistory::new);
+        }
+        /// Records a signal of this key at the current time of `clock`.
+        ///
+        /// In phi-accrual mode, the interval since the previous signal is recorded as well.
+        pub fn heartbeat(&mut self, clock: &dyn Clock) {
+            let now = clock.now();
+            if let Some(heartbeat_history) = &mut self.heartbeat_history {
+                heartbeat_history
+                    .record(now.saturating_duration_since(self.time_out.get_last_instant_update()));
+            }
+            self.time_out.update_timer(clock);
+        }
+        /// Returns the suspicion level of this key at `now`, in phi-accrual mode.
+        #[must_use]
+        pub fn phi(&self, now: Instant) -> Option<f64> {
+            self.heartbeat_history.as_ref().map(|heartbeat_history| {
+                heartbeat_history
+                    .phi(now.saturating_duration_since(self.time_out.get_last_instant_update()))
+            })
+        }
+        /// Returns the instant at which this key times out if it stays silent,
+        /// or `None` if the deadline is too far away to be represented.
+        #[must_use]
+        pub fn deadline(&self) -> Option<Instant> {
+            let first_seen = self.time_out.first_seen();
+            let idle_deadline = self.idle_deadline();
+            match self.expiry_policy {
+                ExpiryPolicy::Sliding => idle_deadline,
+                ExpiryPolicy::Absolute => first_seen.checked_add(self.time_out_duration),
+                ExpiryPolicy::SlidingWithCap(cap) => {
+                    match (idle_deadline, first_seen.checked_add(cap)) {
+                        (Some(idle), Some(cap)) => Some(idle.min(cap)),
+                        (idle, cap) => idle.or(cap),
+                    }
+                }
+            }
+        }
+        /// Returns the instant at which this key times out for its silence: after the timeout
+        /// duration, or, in phi-accrual mode, when phi reaches the threshold if it is earlier.
+        fn idle_deadline(&self) -> Option<Instant> {
+            let last_seen = self.time_out.get_last_instant_update();
+            let fixed_deadline = last_seen.checked_add(self.time_out_duration);
+            let Some(heartbeat_history) = &self.heartbeat_history else {
+                return fixed_deadline;
+            };
+            match (
+                last_seen.checked_add(heartbeat_history.time_to_threshold()),
+                fixed_deadline,
+            ) {
+                (Some(phi_deadline), Some(fixed_deadline)) => {
+                    Some(phi_deadline.min(fixed_deadline))
+                }
+                (phi_deadline, fixed_deadline) => phi_deadline.or(fixed_deadline),
+            }
+        }
+        /// Returns the inactivity duration after which this key becomes suspect, if any.
+        #[must_use]
+        pub fn suspect_duration(&self) -> Option<Duration> {
+            self.suspect_duration
+        }
+        /// Replaces the inactivity duration after which this key becomes suspect.
+        pub fn set_suspect_duration(&mut self, suspect_duration: Option<Duration>) {
+            self.suspect_duration = suspect_duration;
+        }
+        /// Returns the instant at which this key becomes suspect if it stays silent, or
+        /// `None` if it is already suspect or has no suspect duration.
+        #[must_use]
+        pub fn suspect_deadline(&self) -> Option<Instant> {
+            if self.suspect {
+                return None;
             }
+            self.time_out
+                .get_last_instant_update()
+                .checked_add(self.suspect_duration?)
+        }
+        /// Returns `true` if this key is not suspect yet, but has to be at `now`.
+        #[must_use]
+        pub fn becomes_suspect(&self, now: Instant) -> bool {
+            self.suspect_deadline()
+                .is_some_and(|suspect_deadline| now >= suspect_deadline)
+        }
+        /// Marks this key as suspect.
+        pub fn suspect(&mut self) {
+            self.suspect = true;
+        }
+        /// Returns `true` if this key is suspect.
+        #[must_use]
+        pub fn is_suspect(&self) -> bool {
+            self.suspect
+        }
+        /// Clears the suspect state of this key, and returns `true` if it was suspect.
+        pub fn recover(&mut self) -> bool {
+            std::mem::take(&mut self.suspect)
+        }
+        /// Returns the next instant at which the loop has to check this key: when it
+        /// becomes suspect, or when it times out.
+        #[must_use]
+        pub fn next_check(&self) -> Option<Instant> {
+            match (self.suspect_deadline(), self.deadline()) {
+                (Some(suspect_deadline), Some(deadline)) => Some(suspect_deadline.min(deadline)),
+                (suspect_deadline, deadline) => suspect_deadline.or(deadline),
+            }
+        }
+        /// Returns the deadline under which this key was last pushed in the deadline queue.
+        #[must_use]
+        pub fn scheduled(&self) -> Option<Instant> {
+            self.scheduled
+        }
+        /// Records the next check of this key as the scheduled one and returns it, so the
+        /// caller can push the key in the deadline queue.
+        ///
+        /// Returns `None` if the key has no representable deadline and never needs a check.
+        pub fn schedule(&mut self) -> Option<Instant> {
+            self.scheduled = self.next_check();
+            self.scheduled
+        }
+        /// Returns `true` if the next check moved before the scheduled one, in which case the
+        /// key has to be scheduled again to time out on time.
+        #[must_use]
+        pub fn needs_earlier_schedule(&self) -> bool {
+            match (self.next_check(), self.scheduled) {
+                (Some(deadline), Some(scheduled)) => deadline < scheduled,
+                (Some(_), None) => true,
+                (None, _) => false,
+            }
+        }
+        /// Returns `true` if the deadline of this key is reached at `now`.
+        #[must_use]
+        pub fn is_timed_out(&self, now: Instant) -> bool {
+            self.deadline().is_some_and(|deadline| now >= deadline)
         }
         /// Marks this status as expired.
         ///
-        /// This is called internally when a timeout is detected.
+        /// This is called internally when a timeout is detected, as the key leaves the map.
         pub fn expired(&mut self) {
             self.expired = true;
         }
         /// Returns `true` if this status has been marked as expired.
-        ///
-        /// This can be used to skip already-handled entries in the timeout loop.
         #[must_use]
         pub fn is_expired(&self) -> bool {
             self.expired
@@ -255,11 +1499,49 @@ mod timer_status {
             &self.time_out
         }
     }
+    /// The state of a tracked key, as returned by `SandClock::iter()`.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct EntryInfo {
+        /// Instant of the insertion of the key.
+        pub first_seen: Instant,
+        /// Instant of the last signal of the key.
+        pub last_seen: Instant,
+        /// Number of signals of the key, its insertion included.
+        pub heartbeats: u64,
+        /// Instant at which the key times out if it stays silent, `None` if too far away.
+        pub deadline: Option<Instant>,
+        /// Whether the key has been reported with `ClockEvent::Suspect`.
+        pub suspect: bool,
+    }
+
+    impl EntryInfo {
+        /// Reads the state of a key from its timer status.
+        #[must_use]
+        pub fn new(timer_status: &TimerStatus) -> Self {
+            let timer = timer_status.time_out_info();
+            Self {
+                first_seen: timer.first_seen(),
+                last_seen: timer.get_last_instant_update(),
+                heartbeats: timer.heartbeats(),
+                deadline: timer_status.deadline(),
+                suspect: timer_status.is_suspect(),
+            }
+        }
+        /// Time left at `now` before the key times out if it stays silent.
+        #[must_use]
+        pub fn time_remaining(&self, now: Instant) -> Duration {
+            self.deadline.map_or(Duration::MAX, |deadline| {
+                deadline.saturating_duration_since(now)
+            })
+        }
+    }
 }
 
 mod time_out {
     use std::time::Instant;
 
+    use crate::clock::Clock;
+
     /// Lightweight timer used internally by [`TimerStatus`] to track activity timestamps.
     ///
     /// `Timer` holds the last known [`Instant`] at which the associated entity signaled activity.
@@ -269,59 +1551,197 @@ mod time_out {
     /// This struct is intended for internal use within [`SandClock`].
     #[derive(Clone)]
     pub struct Timer {
+        first_update: Instant,
         last_update: Instant,
+        heartbeats: u64,
     }
     impl Default for Timer {
         fn default() -> Self {
-            Self {
-                last_update: Instant::now(),
-            }
+            Self::with_last_update(Instant::now())
         }
     }
     impl Timer {
-        /// Creates a new `Timer` initialized with the current time (`Instant::now()`).
-        pub fn new() -> Self {
+        /// Creates a new `Timer` initialized with the current time of `clock`.
+        pub fn new(clock: &dyn Clock) -> Self {
+            Self::with_last_update(clock.now())
+        }
+        /// Creates a `Timer` whose first and last update happened at `last_update`.
+        pub fn with_last_update(last_update: Instant) -> Self {
             Self {
-                last_update: Instant::now(),
+                first_update: last_update,
+                last_update,
+                heartbeats: 1,
             }
         }
+        /// Creates a `Timer` created at `first_update`, and last updated at `last_update`.
+        pub fn with_updates(first_update: Instant, last_update: Instant) -> Self {
+            Self {
+                first_update,
+                last_update,
+                heartbeats: 1,
+            }
+        }
+        /// Returns the [`Instant`] at which the timer was created.
+        pub fn first_seen(&self) -> Instant {
+            self.first_update
+        }
+        /// Returns the number of recorded updates, the creation of the timer included.
+        pub fn heartbeats(&self) -> u64 {
+            self.heartbeats
+        }
         /// Returns the [`Instant`] of the last recorded update.
         ///
         /// This can be used to compute the elapsed time since the last activity signal.
         pub fn get_last_instant_update(&self) -> Instant {
             self.last_update
         }
-        /// Updates the internal timestamp to the current time.
+        /// Updates the internal timestamp to the current time of `clock`.
         ///
         /// This should be called whenever the entity associated with this timer signals activity.
-        pub fn update_timer(&mut self) {
-            self.last_update = Instant::now();
+        pub fn update_timer(&mut self, clock: &dyn Clock) {
+            self.last_update = clock.now();
+            self.heartbeats = self.heartbeats.saturating_add(1);
         }
     }
 }
 
 mod time_update {
-    use std::fmt::{Debug, Display};
+    use std::{
+        fmt::{Debug, Display},
+        sync::Arc,
+        time::{Duration, Instant},
+    };
 
     use crate::SandClockInsertion;
 
-    #[derive(Clone, Debug)]
-    pub enum ClockEventIntern<K: SandClockInsertion> {
-        TimeOutIntern(K),
-        SandClockDrop,
-    }
+    use super::timer_status::TimerStatus;
+
+    /// Stream of the [`ClockEvent`]s of a [`SandClock<K, V>`](crate::SandClock), see
+    /// `SandClock::events()`.
+    #[cfg(feature = "stream")]
+    pub type EventStream<K, V = ()> = futures::channel::mpsc::Receiver<ClockEvent<K, V>>;
+
+    /// Receiver of the [`ClockEvent`]s of a [`SandClock<K, V>`](crate::SandClock) built with
+    /// `SandClockBuilder::build_with_receiver()`.
+    pub type EventReceiver<K, V = ()> = crossbeam_channel::Receiver<ClockEvent<K, V>>;
+
+    /// Shared callback receiving the [`ClockEvent`]s of a [`SandClock<K, V>`](crate::SandClock).
+    pub type TimeOutCallBack<K, V> = Arc<dyn Fn(ClockEvent<K, V>) + Send + Sync + 'static>;
+
+    /// Event passed to the callback set with `SandClockBuilder::set_time_out_event`.
+    ///
+    /// `TimeOut` carries the expired key and the history of its timer in a [`TimeOutInfo`],
+    /// together with the value it was stored with (`()` for a [`SandClock<K>`](crate::SandClock)
+    /// without values).
+    ///
+    /// `Suspect` and `Recovered` are only sent by a clock with a suspect duration, see
+    /// `SandClockBuilder::set_suspect_duration()`.
     #[derive(Clone, Copy, Debug)]
-    pub enum ClockEvent<K: SandClockInsertion> {
-        TimeOut(K),
+    pub enum ClockEvent<K: SandClockInsertion, V = ()> {
+        TimeOut(TimeOutInfo<K>, V),
+        /// The key has been silent for the suspect duration, and will time out if it
+        /// stays silent.
+        Suspect(K),
+        /// A suspect key has signaled again.
+        Recovered(K),
+        /// The key has been inserted, see [`SandClockBuilder::set_lifecycle_events()`].
+        ///
+        /// [`SandClockBuilder::set_lifecycle_events()`]: super::SandClockBuilder::set_lifecycle_events
+        Inserted(K),
+        /// The key has signaled, see [`SandClockBuilder::set_refreshed_events()`].
+        ///
+        /// [`SandClockBuilder::set_refreshed_events()`]: super::SandClockBuilder::set_refreshed_events
+        Refreshed(K),
+        /// The key has been removed explicitly, see
+        /// [`SandClockBuilder::set_lifecycle_events()`].
+        ///
+        /// [`SandClockBuilder::set_lifecycle_events()`]: super::SandClockBuilder::set_lifecycle_events
+        Removed(K),
         SandClockDrop,
     }
 
-    impl<K: SandClockInsertion> Display for ClockEvent<K> {
+    /// The expired key of a [`ClockEvent::TimeOut`], with the history of its timer.
+    ///
+    /// A key restored from a snapshot keeps the instant of its insertion, but its heartbeats
+    /// are counted again from one.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct TimeOutInfo<K> {
+        pub key: K,
+        /// Instant of the insertion of the key.
+        pub first_seen: Instant,
+        /// Instant of the last signal of the key.
+        pub last_seen: Instant,
+        /// Number of signals of the key, its insertion included.
+        pub heartbeats: u64,
+        /// Instant at which the key was due to time out.
+        pub deadline: Instant,
+        /// Instant of the polling cycle that expired the key.
+        pub expired_at: Instant,
+    }
+
+    impl<K> TimeOutInfo<K> {
+        /// Collects the history of `key` from its timer status, expired at `expired_at`.
+        #[must_use]
+        pub fn new(key: K, timer_status: &TimerStatus, expired_at: Instant) -> Self {
+            let timer = timer_status.time_out_info();
+            Self {
+                key,
+                first_seen: timer.first_seen(),
+                last_seen: timer.get_last_instant_update(),
+                heartbeats: timer.heartbeats(),
+                deadline: timer_status.deadline().unwrap_or(expired_at),
+                expired_at,
+            }
+        }
+        /// Time between the first and the last signal of the key.
+        #[must_use]
+        pub fn lifetime(&self) -> Duration {
+            self.last_seen.saturating_duration_since(self.first_seen)
+        }
+        /// How late the expiry fired compared with the deadline of the key.
+        #[must_use]
+        pub fn lateness(&self) -> Duration {
+            self.expired_at.saturating_duration_since(self.deadline)
+        }
+    }
+
+    impl<K: SandClockInsertion, V> ClockEvent<K, V> {
+        /// Returns the name of the variant, e.g. `timeout`.
+        #[must_use]
+        pub fn kind(&self) -> &'static str {
+            match self {
+                Self::TimeOut(..) => "timeout",
+                Self::Suspect(_) => "suspect",
+                Self::Recovered(_) => "recovered",
+                Self::Inserted(_) => "inserted",
+                Self::Refreshed(_) => "refreshed",
+                Self::Removed(_) => "removed",
+                Self::SandClockDrop => "drop",
+            }
+        }
+    }
+
+    impl<K: SandClockInsertion, V> Display for ClockEvent<K, V> {
         fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
             match self {
-                Self::TimeOut(_k) => {
+                Self::TimeOut(_k, _v) => {
                     write!(f, "Connnection timout ! ")
                 }
+                Self::Suspect(_k) => {
+                    write!(f, "Connection suspect")
+                }
+                Self::Recovered(_k) => {
+                    write!(f, "Connection recovered")
+                }
+                Self::Inserted(_k) => {
+                    write!(f, "Connection inserted")
+                }
+                Self::Refreshed(_k) => {
+                    write!(f, "Connection refreshed")
+                }
+                Self::Removed(_k) => {
+                    write!(f, "Connection removed")
+                }
                 Self::SandClockDrop => {
                     write!(f, "SandClockDrop has dropped")
                 }